@@ -0,0 +1,372 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+pub use self::compliance_module::{Compliance, ComplianceModule};
+
+#[ink::contract]
+mod compliance_module {
+
+    use identity_registry::{IdentityRegistry, IdentityVerifier};
+    use ink_env::call::FromAccountId;
+    use ink_storage::{collections::HashMap as StorageHashMap, lazy::Lazy};
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        OnlyOwner,
+        OnlyToken,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// Hooks a security token calls on its compliance modules. Issuance is reported as a
+    /// transfer from the token contract's own account, redemption as a transfer out of the holder.
+    #[ink::trait_definition]
+    pub trait Compliance {
+        /// Whether a transfer satisfies every rule, called by the token before moving balances.
+        #[ink(message)]
+        fn check_transfer(&self, from: AccountId, to: AccountId, amount: Balance) -> bool;
+
+        /// Record a completed transfer, only by the bound token.
+        #[ink(message)]
+        fn transferred(&mut self, from: AccountId, to: AccountId, amount: Balance) -> Result<()>;
+
+        /// Record the whole balance of a holder while the token seeds a newly added module,
+        /// replacing what was recorded for it before, only by the bound token.
+        #[ink(message)]
+        fn seed(&mut self, holder: AccountId, balance: Balance) -> Result<()>;
+    }
+
+    /// Transfer rules of one security token. A rule set to zero or left empty is not enforced.
+    #[ink(storage)]
+    pub struct ComplianceModule {
+        ///Owner of Contract.
+        owner: Lazy<AccountId>,
+        /// Token allowed to report transfers.
+        token: Lazy<Option<AccountId>>,
+        /// Balances as reported through `transferred`, existing balances are seeded
+        /// by the token in batches when the module is added.
+        balances: StorageHashMap<AccountId, Balance>,
+        /// Accounts with a non-zero balance in `balances`.
+        holder_count: Lazy<u32>,
+        /// Most accounts that may hold the token at once.
+        max_holders: Lazy<u32>,
+        /// Most tokens a single investor may hold.
+        max_balance: Lazy<Balance>,
+        /// Time before which an account may not send tokens.
+        locked_until: StorageHashMap<AccountId, u64>,
+        /// Registry receivers' countries are looked up in.
+        identity_registry: Lazy<Option<AccountId>>,
+        /// Countries whose investors may not receive tokens.
+        blocked_countries: StorageHashMap<u16, bool>,
+    }
+
+    #[ink(event)]
+    pub struct TransferRecorded {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BalanceSeeded {
+        #[ink(topic)]
+        holder: AccountId,
+        balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TokenUpdated {
+        token: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct MaxHoldersUpdated {
+        max_holders: u32,
+    }
+
+    #[ink(event)]
+    pub struct MaxBalanceUpdated {
+        max_balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LockupUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        until: u64,
+    }
+
+    #[ink(event)]
+    pub struct IdentityRegistryUpdated {
+        registry: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct CountryBlockedUpdated {
+        #[ink(topic)]
+        country: u16,
+        blocked: bool,
+    }
+
+    impl Compliance for ComplianceModule {
+        #[ink(message)]
+        fn check_transfer(&self, from: AccountId, to: AccountId, amount: Balance) -> bool {
+            let now = self.env().block_timestamp();
+            if now < *self.locked_until.get(&from).unwrap_or(&0) {
+                return false;
+            }
+
+            let to_balance = self.balance_of(to);
+            if *self.max_balance > 0 && to_balance.saturating_add(amount) > *self.max_balance {
+                return false;
+            }
+            if *self.max_holders > 0 && to_balance == 0 && amount > 0 && from != to {
+                let from_balance = self.balance_of(from);
+                let leaving = from_balance > 0 && amount >= from_balance;
+                if !leaving && *self.holder_count >= *self.max_holders {
+                    return false;
+                }
+            }
+
+            if let Some(registry) = *self.identity_registry {
+                let registry: IdentityRegistry = FromAccountId::from_account_id(registry);
+                match registry.investor_country(to) {
+                    Some(country) if !self.is_country_blocked(country) => {}
+                    _ => return false,
+                }
+            }
+            true
+        }
+
+        #[ink(message)]
+        fn transferred(&mut self, from: AccountId, to: AccountId, amount: Balance) -> Result<()> {
+            if Some(self.env().caller()) != *self.token {
+                return Err(Error::OnlyToken);
+            }
+
+            let from_balance = self.balance_of(from);
+            if from_balance > 0 && amount >= from_balance {
+                *self.holder_count = self.holder_count.saturating_sub(1);
+            }
+            self.balances.insert(from, from_balance.saturating_sub(amount));
+
+            let to_balance = self.balance_of(to);
+            if to_balance == 0 && amount > 0 {
+                *self.holder_count += 1;
+            }
+            self.balances.insert(to, to_balance + amount);
+
+            self.env().emit_event(TransferRecorded { from, to, amount });
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn seed(&mut self, holder: AccountId, balance: Balance) -> Result<()> {
+            if Some(self.env().caller()) != *self.token {
+                return Err(Error::OnlyToken);
+            }
+
+            let recorded = self.balance_of(holder);
+            if recorded == 0 && balance > 0 {
+                *self.holder_count += 1;
+            } else if recorded > 0 && balance == 0 {
+                *self.holder_count = self.holder_count.saturating_sub(1);
+            }
+            self.balances.insert(holder, balance);
+
+            self.env().emit_event(BalanceSeeded { holder, balance });
+            Ok(())
+        }
+    }
+
+    impl ComplianceModule {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            let caller = Self::env().caller();
+            Self {
+                owner: Lazy::new(caller),
+                token: Lazy::new(None),
+                balances: StorageHashMap::new(),
+                holder_count: Lazy::new(0),
+                max_holders: Lazy::new(0),
+                max_balance: Lazy::new(0),
+                locked_until: StorageHashMap::new(),
+                identity_registry: Lazy::new(None),
+                blocked_countries: StorageHashMap::new(),
+            }
+        }
+
+        ///Bind the token whose transfers this module records, only by owner.
+        #[ink(message)]
+        pub fn set_token(&mut self, token: Option<AccountId>) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.token = token;
+            self.env().emit_event(TokenUpdated { token });
+            Ok(())
+        }
+
+        ///Set the most accounts that may hold the token, zero for no limit, only by owner.
+        #[ink(message)]
+        pub fn set_max_holders(&mut self, max_holders: u32) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.max_holders = max_holders;
+            self.env().emit_event(MaxHoldersUpdated { max_holders });
+            Ok(())
+        }
+
+        ///Set the most tokens one investor may hold, zero for no limit, only by owner.
+        #[ink(message)]
+        pub fn set_max_balance(&mut self, max_balance: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.max_balance = max_balance;
+            self.env().emit_event(MaxBalanceUpdated { max_balance });
+            Ok(())
+        }
+
+        ///Stop account from sending tokens until the given time, only by owner.
+        #[ink(message)]
+        pub fn set_lockup(&mut self, account: AccountId, until: u64) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.locked_until.insert(account, until);
+            self.env().emit_event(LockupUpdated { account, until });
+            Ok(())
+        }
+
+        ///Set registry receivers' countries are looked up in, None to disable, only by owner.
+        #[ink(message)]
+        pub fn set_identity_registry(&mut self, registry: Option<AccountId>) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.identity_registry = registry;
+            self.env().emit_event(IdentityRegistryUpdated { registry });
+            Ok(())
+        }
+
+        ///Block or allow receivers from a country, only by owner.
+        #[ink(message)]
+        pub fn set_country_blocked(&mut self, country: u16, blocked: bool) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.blocked_countries.insert(country, blocked);
+            self.env().emit_event(CountryBlockedUpdated { country, blocked });
+            Ok(())
+        }
+
+        ///get whether receivers from a country are blocked
+        #[ink(message)]
+        pub fn is_country_blocked(&self, country: u16) -> bool {
+            *self.blocked_countries.get(&country).unwrap_or(&false)
+        }
+
+        #[ink(message)]
+        pub fn balance_of(&self, account: AccountId) -> Balance {
+            *self.balances.get(&account).unwrap_or(&0)
+        }
+
+        #[ink(message)]
+        pub fn holder_count(&self) -> u32 {
+            *self.holder_count
+        }
+
+        #[ink(message)]
+        pub fn lockup_of(&self, account: AccountId) -> u64 {
+            *self.locked_until.get(&account).unwrap_or(&0)
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, to: AccountId) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.owner = to;
+            Ok(())
+        }
+
+        fn only_owner(&self, caller: AccountId) -> Result<()> {
+            if *self.owner == caller {
+                Ok(())
+            } else {
+                Err(Error::OnlyOwner)
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        use ink_lang as ink;
+
+        #[ink::test]
+        fn max_holders_works() {
+            let mut contract = ComplianceModule::new();
+            let token = AccountId::from([0x1; 32]);
+            let alice = AccountId::from([0x2; 32]);
+            let bob = AccountId::from([0x3; 32]);
+            let carol = AccountId::from([0x4; 32]);
+            assert_eq!(contract.transferred(token, alice, 10), Err(Error::OnlyToken));
+            assert_eq!(contract.set_token(Some(token)), Ok(()));
+            assert_eq!(contract.set_max_holders(2), Ok(()));
+
+            assert_eq!(contract.transferred(token, alice, 10), Ok(()));
+            assert_eq!(contract.transferred(token, bob, 10), Ok(()));
+            assert_eq!(contract.holder_count(), 2);
+            assert!(!contract.check_transfer(alice, carol, 5));
+            assert!(contract.check_transfer(alice, carol, 10));
+            assert!(contract.check_transfer(alice, bob, 5));
+
+            assert_eq!(contract.transferred(alice, carol, 10), Ok(()));
+            assert_eq!(contract.holder_count(), 2);
+            assert_eq!(contract.balance_of(carol), 10);
+        }
+
+        #[ink::test]
+        fn seed_replaces_recorded_balance() {
+            let mut contract = ComplianceModule::new();
+            let token = AccountId::from([0x1; 32]);
+            let alice = AccountId::from([0x2; 32]);
+            let bob = AccountId::from([0x3; 32]);
+            assert_eq!(contract.seed(alice, 10), Err(Error::OnlyToken));
+            assert_eq!(contract.set_token(Some(token)), Ok(()));
+
+            // A transfer recorded before alice was seeded is overwritten, not added to.
+            assert_eq!(contract.transferred(bob, alice, 5), Ok(()));
+            assert_eq!(contract.seed(alice, 10), Ok(()));
+            assert_eq!(contract.seed(alice, 10), Ok(()));
+            assert_eq!(contract.balance_of(alice), 10);
+            assert_eq!(contract.holder_count(), 1);
+
+            assert_eq!(contract.seed(alice, 0), Ok(()));
+            assert_eq!(contract.holder_count(), 0);
+        }
+
+        #[ink::test]
+        fn max_balance_and_lockup_works() {
+            let mut contract = ComplianceModule::new();
+            let alice = AccountId::from([0x2; 32]);
+            let bob = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_max_balance(100), Ok(()));
+            assert!(contract.check_transfer(alice, bob, 100));
+            assert!(!contract.check_transfer(alice, bob, 101));
+
+            assert_eq!(contract.set_lockup(alice, u64::MAX), Ok(()));
+            assert!(!contract.check_transfer(alice, bob, 1));
+            assert_eq!(contract.set_lockup(alice, 0), Ok(()));
+            assert!(contract.check_transfer(alice, bob, 1));
+        }
+
+        #[ink::test]
+        fn config_setters_emit_events() {
+            let mut contract = ComplianceModule::new();
+            let alice = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_token(Some(alice)), Ok(()));
+            assert_eq!(contract.set_max_holders(10), Ok(()));
+            assert_eq!(contract.set_max_balance(100), Ok(()));
+            assert_eq!(contract.set_lockup(alice, 5), Ok(()));
+            assert_eq!(contract.set_identity_registry(None), Ok(()));
+            assert_eq!(contract.set_country_blocked(840, true), Ok(()));
+            assert!(contract.is_country_blocked(840));
+            assert_eq!(ink_env::test::recorded_events().count(), 6);
+        }
+    }
+}
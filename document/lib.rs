@@ -0,0 +1,13 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_env::Hash;
+use ink_prelude::string::String;
+use ink_storage::traits::{PackedLayout, SpreadLayout};
+
+/// Off-chain document referenced by its uri and pinned by the hash of its content.
+#[derive(Debug, PartialEq, Eq, scale::Encode, PackedLayout, scale::Decode, SpreadLayout, Clone)]
+#[cfg_attr( feature = "std", derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout))]
+pub struct Document {
+    pub doc_uri: String,
+    pub doc_hash: Hash,
+}
@@ -7,9 +7,17 @@ mod erc1400 {
     use super::*;
     use models::{ doc::*, error::*};
 
+    use compliance_module::{Compliance, ComplianceModule};
+    use identity_registry::{IdentityRegistry, IdentityVerifier};
+    use ink_env::call::FromAccountId;
     use ink_prelude::{string::String, vec::Vec};
     use ink_storage::{collections::HashMap as StorageHashMap, Lazy };
 
+    /// Most compliance modules a token may check transfers against.
+    const MAX_COMPLIANCE_MODULES: usize = 8;
+    /// Most holders a single `seed_compliance_module` call seeds.
+    const MAX_SEED_BATCH: usize = 50;
+
     #[ink(storage)]
     pub struct Erc1400 {
         symbol: Vec<String>,
@@ -24,6 +32,10 @@ mod erc1400 {
         allow_by_partition: StorageHashMap<(AccountId, Hash), Balance >,
         controllers: StorageHashMap<(AccountId, Hash), bool>,
         is_issuable: StorageHashMap<Hash, bool>,
+        identity_registry: Option<AccountId>,
+        compliance_modules: Vec<AccountId>,
+        seeding_module: Option<AccountId>,
+        added_modules: StorageHashMap<AccountId, bool>,
     }
 
     impl Erc1400 {
@@ -45,6 +57,10 @@ mod erc1400 {
                 controllers: StorageHashMap::new(),
                 allow_by_partition: StorageHashMap::new(),
                 is_issuable: StorageHashMap::new(),
+                identity_registry: None,
+                compliance_modules: Vec::new(),
+                seeding_module: None,
+                added_modules: StorageHashMap::new(),
             }
         }
 
@@ -56,6 +72,107 @@ mod erc1400 {
             }
         }
 
+        ///set identity registry that receivers must be verified by, None to disable
+        #[ink(message)]
+        pub fn set_identity_registry(&mut self, registry: Option<AccountId>) -> Result<(), Error> {
+            if self.only_owner() {
+                self.identity_registry = registry;
+                Ok(())
+            }else {
+                Err(Error::NotAllowed)
+            }
+        }
+
+        ///get identity registry
+        #[ink(message)]
+        pub fn identity_registry(&self) -> Option<AccountId> {
+            self.identity_registry
+        }
+
+        ///add compliance module contract, the module must already be bound to this token
+        ///it records transfers at once, is seeded with existing balances by seed_compliance_module
+        ///and checks transfers once activate_compliance_module is called, a module is added only once
+        #[ink(message)]
+        pub fn add_compliance_module(&mut self, module: AccountId) -> Result<(), Error> {
+            if self.only_owner() {
+                if self.added_modules.contains_key(&module) {
+                    return Err(Error::ModuleAlreadyAdded);
+                }
+                if self.seeding_module.is_some() {
+                    return Err(Error::SeedingInProgress);
+                }
+                if self.compliance_modules.len() >= MAX_COMPLIANCE_MODULES {
+                    return Err(Error::TooManyModules);
+                }
+
+                self.added_modules.insert(module, true);
+                self.seeding_module = Some(module);
+                Ok(())
+            }else {
+                Err(Error::NotAllowed)
+            }
+        }
+
+        ///seed the module being added with balances of up to MAX_SEED_BATCH holders
+        ///seeding a holder again replaces its seeded balance
+        #[ink(message)]
+        pub fn seed_compliance_module(&mut self, holders: Vec<AccountId>) -> Result<(), Error> {
+            if self.only_owner() {
+                let module = self.seeding_module.ok_or(Error::NoModuleSeeding)?;
+                if holders.len() > MAX_SEED_BATCH {
+                    return Err(Error::SeedBatchTooLarge);
+                }
+
+                let mut hooks: ComplianceModule = FromAccountId::from_account_id(module);
+                for holder in holders {
+                    hooks
+                        .seed(holder, self.total_balance_of(holder))
+                        .map_err(|_| Error::ModuleRefused)?;
+                }
+                Ok(())
+            }else {
+                Err(Error::NotAllowed)
+            }
+        }
+
+        ///start checking transfers against the seeded module
+        #[ink(message)]
+        pub fn activate_compliance_module(&mut self) -> Result<(), Error> {
+            if self.only_owner() {
+                let module = self.seeding_module.take().ok_or(Error::NoModuleSeeding)?;
+                self.compliance_modules.push(module);
+                Ok(())
+            }else {
+                Err(Error::NotAllowed)
+            }
+        }
+
+        ///remove compliance module contract
+        #[ink(message)]
+        pub fn remove_compliance_module(&mut self, module: AccountId) -> Result<(), Error> {
+            if self.only_owner() {
+                self.compliance_modules.retain(|m| *m != module);
+                if self.seeding_module == Some(module) {
+                    self.seeding_module = None;
+                }
+                Ok(())
+            }else {
+                Err(Error::NotAllowed)
+            }
+        }
+
+        ///get module being seeded
+        #[ink(message)]
+        pub fn seeding_module(&self) -> Option<AccountId> {
+            self.seeding_module
+        }
+
+        ///get list of compliance module
+        #[ink(message)]
+        pub fn compliance_modules(&self) -> Vec<AccountId> {
+            self.compliance_modules.clone()
+        }
+
         ///get total token amount of all partition 
         #[ink(message)]
         pub fn total_supply(&self) -> Balance {
@@ -74,6 +191,15 @@ mod erc1400 {
             self.balance_of_partition.get(&(token_holder, partition)).copied().unwrap_or(0)
         }
 
+        ///get total balance of token_holder over all partition
+        #[ink(message)]
+        pub fn total_balance_of(&self, token_holder: AccountId) -> Balance {
+            self.partion_of_token_holder(token_holder)
+                .iter()
+                .map(|partition| self.balance_of_by_partition(token_holder, *partition))
+                .sum()
+        }
+
         ///get list of total partition of each token_holder
         #[ink(message)]
         pub fn partion_of_token_holder(&self, token_holder: AccountId) -> Vec<Hash>{
@@ -152,6 +278,11 @@ mod erc1400 {
         pub fn issue_by_partition(&mut self, partition: Hash, amount: Balance) -> Result<(), Error> {
             let caller = self.env().caller();
             if self.is_controller_by_partition(partition) && self.is_issuable(partition) {
+                let token = self.env().account_id();
+                if self.compliance_allows(token, caller, amount) == false {
+                    return Err(Error::ComplianceRejected);
+                }
+                self.notify_compliance(token, caller, amount)?;
                 self.total_supply += amount;
                 self.total_supply_by_partition.insert(partition, amount);
 
@@ -182,11 +313,18 @@ mod erc1400 {
         #[ink(message)]
         pub fn transfer(&mut self, to: AccountId, partition: Hash, amount: Balance) -> Result<(), Error>{
             let caller = self.env().caller();
-            if self.is_allowed(to, partition, amount) {
-                self.transfer_from_to(caller, to,partition, amount)?;
-                Ok(())
-            }else {
-                Err(Error::NotAllowed)
+            self.check_transfer(caller, caller, to, partition, amount).map_err(|(_, reason)| reason)?;
+            self.notify_compliance(caller, to, amount)?;
+            self.transfer_from_to(caller, to,partition, amount)
+        }
+
+        ///check transfer without executing it, returns ERC-1066 status, reason and destination partition
+        ///the transfer is simulated as sent by `from` itself
+        #[ink(message)]
+        pub fn can_transfer(&self, from: AccountId, to: AccountId, partition: Hash, amount: Balance) -> (u8, Option<Error>, Hash) {
+            match self.check_transfer(from, from, to, partition, amount) {
+                Ok(()) => (TRANSFER_SUCCESS, None, partition),
+                Err((status, reason)) => (status, Some(reason), partition),
             }
         }
 
@@ -199,7 +337,10 @@ mod erc1400 {
                 let balance = self.balance_of_by_partition(token_holder, partition);
                 if balance < amount {
                     return Err(Error::InsufficientBalance)
+                }else if self.compliance_allows(token_holder, caller, amount) == false {
+                    return Err(Error::ComplianceRejected)
                 }else {
+                    self.notify_compliance(token_holder, caller, amount)?;
                     self.balance_of_partition.insert((token_holder, partition), balance - amount);
                 }
 
@@ -212,8 +353,75 @@ mod erc1400 {
             }
         }
 
-        fn is_allowed(&self, token_holder: AccountId, partition: Hash, amount: Balance) -> bool {
-            if self.is_controller_by_partition(partition) {
+        /// `operator` is the account sending the transfer, checked for controller and operator rights
+        fn check_transfer(&self, operator: AccountId, from: AccountId, to: AccountId, partition: Hash, amount: Balance) -> Result<(), (u8, Error)> {
+            self.check_transfer_with(operator, from, to, partition, amount, |from, to, amount| self.compliance_allows(from, to, amount))
+        }
+
+        /// `check_transfer` with the compliance module calls replaced by `compliant`
+        fn check_transfer_with(
+            &self,
+            operator: AccountId,
+            from: AccountId,
+            to: AccountId,
+            partition: Hash,
+            amount: Balance,
+            compliant: impl Fn(AccountId, AccountId, Balance) -> bool,
+        ) -> Result<(), (u8, Error)> {
+            if self.is_allowed(operator, to, partition, amount) == false {
+                return Err((INVALID_RECEIVER, Error::NotAllowed));
+            }
+            if self.is_verified(from) == false {
+                return Err((INVALID_SENDER, Error::IdentityNotVerified));
+            }
+            if self.is_verified(to) == false {
+                return Err((INVALID_RECEIVER, Error::IdentityNotVerified));
+            }
+            if self.balance_of_by_partition(from, partition) < amount {
+                return Err((INSUFFICIENT_BALANCE, Error::InsufficientBalance));
+            }
+            if compliant(from, to, amount) == false {
+                return Err((TRANSFER_FAILURE, Error::ComplianceRejected));
+            }
+            Ok(())
+        }
+
+        fn compliance_allows(&self, from: AccountId, to: AccountId, amount: Balance) -> bool {
+            self.compliance_modules.iter().all(|module| {
+                let module: ComplianceModule = FromAccountId::from_account_id(*module);
+                module.check_transfer(from, to, amount)
+            })
+        }
+
+        /// Let every module, including the one being seeded, record a balance change before
+        /// balances move. When a module refuses, the modules that already recorded it are reverted.
+        fn notify_compliance(&self, from: AccountId, to: AccountId, amount: Balance) -> Result<(), Error> {
+            let modules = self.compliance_modules.iter().chain(self.seeding_module.iter());
+            for (notified, module) in modules.clone().enumerate() {
+                let mut hooks: ComplianceModule = FromAccountId::from_account_id(*module);
+                if hooks.transferred(from, to, amount).is_err() {
+                    for module in modules.clone().take(notified) {
+                        let mut hooks: ComplianceModule = FromAccountId::from_account_id(*module);
+                        let _ = hooks.transferred(to, from, amount);
+                    }
+                    return Err(Error::ModuleRefused);
+                }
+            }
+            Ok(())
+        }
+
+        fn is_verified(&self, account: AccountId) -> bool {
+            match self.identity_registry {
+                Some(registry) if account != *self.owner => {
+                    let registry: IdentityRegistry = FromAccountId::from_account_id(registry);
+                    registry.is_verified(account)
+                }
+                _ => true,
+            }
+        }
+
+        fn is_allowed(&self, operator: AccountId, token_holder: AccountId, partition: Hash, amount: Balance) -> bool {
+            if self.controllers.get(&(operator, partition)).copied().unwrap_or(false) {
                 true
             }else {
                 let alow_balance = self.get_allowed_amout(token_holder, partition);
@@ -267,4 +475,125 @@ mod erc1400 {
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        use ink_lang as ink;
+
+        fn set_caller(caller: AccountId) {
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            let mut data =
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4]));
+            data.push_arg(&caller);
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller, callee, 1000000, 0, data,
+            );
+        }
+
+        #[ink::test]
+        fn can_transfer_works() {
+            let mut contract = Erc1400::new();
+            let owner = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let carol = AccountId::from([0x3; 32]);
+            let partition = Hash::from([0x7; 32]);
+            assert_eq!(contract.set_controller_by_partition(owner, "KPI".to_string(), partition), Ok(()));
+            assert_eq!(contract.issue_by_partition(partition, 100), Ok(()));
+            assert_eq!(
+                contract.can_transfer(owner, bob, partition, 101),
+                (INSUFFICIENT_BALANCE, Some(Error::InsufficientBalance), partition)
+            );
+            assert_eq!(contract.can_transfer(owner, bob, partition, 10), (TRANSFER_SUCCESS, None, partition));
+            assert_eq!(contract.transfer(bob, partition, 10), Ok(()));
+            assert_eq!(contract.balance_of_by_partition(bob, partition), 10);
+
+            assert_eq!(
+                contract.can_transfer(bob, carol, partition, 5),
+                (INVALID_RECEIVER, Some(Error::NotAllowed), partition)
+            );
+            assert_eq!(contract.set_allow_amount_by_partition(carol, partition, 5), Ok(()));
+            assert_eq!(contract.can_transfer(bob, carol, partition, 5), (TRANSFER_SUCCESS, None, partition));
+        }
+
+        #[ink::test]
+        fn controller_transfer_works() {
+            let mut contract = Erc1400::new();
+            let owner = AccountId::from([0x1; 32]);
+            let controller = AccountId::from([0x2; 32]);
+            let carol = AccountId::from([0x3; 32]);
+            let partition = Hash::from([0x7; 32]);
+            assert_eq!(contract.set_controller_by_partition(controller, "KPI".to_string(), partition), Ok(()));
+
+            // carol holds no allowance, only the controller's own rights let it send to her.
+            set_caller(controller);
+            assert_eq!(contract.issue_by_partition(partition, 100), Ok(()));
+            assert_eq!(contract.transfer(carol, partition, 5), Ok(()));
+            assert_eq!(contract.balance_of_by_partition(carol, partition), 5);
+            assert_eq!(
+                contract.can_transfer(carol, owner, partition, 5),
+                (INVALID_RECEIVER, Some(Error::NotAllowed), partition)
+            );
+
+            set_caller(carol);
+            assert_eq!(contract.transfer(owner, partition, 5), Err(Error::NotAllowed));
+        }
+
+        #[ink::test]
+        fn compliance_module_list_works() {
+            let mut contract = Erc1400::new();
+            let module = AccountId::from([0x9; 32]);
+            assert_eq!(contract.seed_compliance_module(Vec::new()), Err(Error::NoModuleSeeding));
+            assert_eq!(contract.add_compliance_module(module), Ok(()));
+            assert_eq!(contract.seeding_module(), Some(module));
+            assert!(contract.compliance_modules().is_empty());
+            assert_eq!(
+                contract.add_compliance_module(AccountId::from([0xa; 32])),
+                Err(Error::SeedingInProgress)
+            );
+            assert_eq!(
+                contract.seed_compliance_module(vec![module; MAX_SEED_BATCH + 1]),
+                Err(Error::SeedBatchTooLarge)
+            );
+            assert_eq!(contract.seed_compliance_module(Vec::new()), Ok(()));
+            assert_eq!(contract.activate_compliance_module(), Ok(()));
+            assert_eq!(contract.compliance_modules(), vec![module]);
+
+            // A module is seeded once, adding it again would count every balance twice.
+            assert_eq!(contract.add_compliance_module(module), Err(Error::ModuleAlreadyAdded));
+            assert_eq!(contract.remove_compliance_module(module), Ok(()));
+            assert!(contract.compliance_modules().is_empty());
+            assert_eq!(contract.add_compliance_module(module), Err(Error::ModuleAlreadyAdded));
+
+            for i in 0..MAX_COMPLIANCE_MODULES {
+                assert_eq!(contract.add_compliance_module(AccountId::from([i as u8; 32])), Ok(()));
+                assert_eq!(contract.activate_compliance_module(), Ok(()));
+            }
+            assert_eq!(
+                contract.add_compliance_module(AccountId::from([0xa; 32])),
+                Err(Error::TooManyModules)
+            );
+        }
+
+        #[ink::test]
+        fn compliance_rejection_works() {
+            let mut contract = Erc1400::new();
+            let owner = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let partition = Hash::from([0x7; 32]);
+            assert_eq!(contract.set_controller_by_partition(owner, "KPI".to_string(), partition), Ok(()));
+            assert_eq!(contract.issue_by_partition(partition, 100), Ok(()));
+            assert_eq!(
+                contract.check_transfer_with(owner, owner, bob, partition, 10, |_, _, _| false),
+                Err((TRANSFER_FAILURE, Error::ComplianceRejected))
+            );
+            assert_eq!(contract.check_transfer_with(owner, owner, bob, partition, 10, |_, _, _| true), Ok(()));
+            assert_eq!(
+                contract.check_transfer_with(owner, owner, bob, partition, 101, |_, _, _| false),
+                Err((INSUFFICIENT_BALANCE, Error::InsufficientBalance))
+            );
+        }
+    }
 }
@@ -4,4 +4,19 @@ use scale::{Decode, Encode};
 pub enum Error {
     NotAllowed,
     InsufficientBalance,
-}
\ No newline at end of file
+    IdentityNotVerified,
+    ComplianceRejected,
+    TooManyModules,
+    ModuleAlreadyAdded,
+    SeedingInProgress,
+    NoModuleSeeding,
+    SeedBatchTooLarge,
+    ModuleRefused,
+}
+
+/// ERC-1066 status codes returned by `can_transfer`.
+pub const TRANSFER_FAILURE: u8 = 0x50;
+pub const TRANSFER_SUCCESS: u8 = 0x51;
+pub const INSUFFICIENT_BALANCE: u8 = 0x52;
+pub const INVALID_SENDER: u8 = 0x56;
+pub const INVALID_RECEIVER: u8 = 0x57;
@@ -4,23 +4,79 @@ use ink_lang as ink;
 
 #[ink::contract]
 mod erc_1400 {
-    use ink_storage::{collections::HashMap as StorageHashMap, lazy::Lazy};
+    use compliance_module::{Compliance, ComplianceModule};
+    use identity_registry::{IdentityRegistry, IdentityVerifier};
+    use ink_env::call::FromAccountId;
+    use ink_prelude::vec::Vec;
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        lazy::Lazy,
+        traits::{PackedLayout, SpreadLayout},
+    };
+
+    /// ERC-1066 status codes returned by `can_transfer`.
+    pub const TRANSFER_FAILURE: u8 = 0x50;
+    pub const TRANSFER_SUCCESS: u8 = 0x51;
+    pub const INSUFFICIENT_BALANCE: u8 = 0x52;
+    pub const INVALID_SENDER: u8 = 0x56;
+    pub const INVALID_RECEIVER: u8 = 0x57;
+
+    /// The token keeps every balance in this single partition.
+    pub const DEFAULT_PARTITION: [u8; 32] = [0x0; 32];
+    /// Most compliance modules a token may check transfers against.
+    pub const MAX_COMPLIANCE_MODULES: usize = 8;
+    /// Most holders a single `seed_compliance_module` call seeds.
+    pub const MAX_SEED_BATCH: usize = 50;
 
     #[derive(Debug, PartialEq, Eq, scale::Encode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub enum Error {
         InsufficientBalance,
         NotPermission,
+        InvestorExpired,
+        TierTooLow,
+        IdentityNotVerified,
+        ComplianceRejected,
+        UnknownPartition,
+        TooManyModules,
+        ModuleAlreadyAdded,
+        SeedingInProgress,
+        NoModuleSeeding,
+        SeedBatchTooLarge,
+        ModuleRefused,
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
 
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Investor {
+        pub tier: u8,
+        /// ISO 3166-1 numeric country code.
+        pub jurisdiction: u16,
+        /// Time after which the investor must be verified again.
+        pub expires_at: u64,
+    }
+
     #[ink(storage)]
     pub struct Erc1400 {
         owner: Lazy<AccountId>,
         total_supply: Lazy<Balance>,
-        partitions: StorageHashMap<AccountId, bool>,
+        partitions: StorageHashMap<AccountId, Investor>,
         balances: StorageHashMap<AccountId, Balance>,
+        /// Lowest investor tier allowed to send or receive tokens.
+        min_tier: Lazy<u8>,
+        /// Shared identity registry receivers must be verified by, if any.
+        identity_registry: Lazy<Option<AccountId>>,
+        /// Compliance module contracts every transfer is checked against.
+        compliance_modules: Vec<AccountId>,
+        /// Module being seeded with existing balances, notified of transfers but not checked yet.
+        seeding_module: Lazy<Option<AccountId>>,
+        /// Every module ever added, a module is seeded only once.
+        added_modules: StorageHashMap<AccountId, bool>,
     }
 
     impl Erc1400 {
@@ -34,14 +90,43 @@ mod erc_1400 {
                 total_supply: Lazy::new(initial_supply),
                 partitions: StorageHashMap::new(),
                 balances,
+                min_tier: Lazy::new(0),
+                identity_registry: Lazy::new(None),
+                compliance_modules: Vec::new(),
+                seeding_module: Lazy::new(None),
+                added_modules: StorageHashMap::new(),
             }
         }
 
         #[ink(message)]
-        pub fn add_partition(&mut self, parititon: AccountId) {
+        pub fn add_partition(
+            &mut self,
+            parititon: AccountId,
+            tier: u8,
+            jurisdiction: u16,
+            expires_at: u64,
+        ) {
             let caller = self.env().caller();
             if caller == *self.owner {
-                self.partitions.insert(parititon, true);
+                self.partitions.insert(parititon, Investor {
+                    tier,
+                    jurisdiction,
+                    expires_at,
+                });
+            }
+        }
+
+        ///Whitelist a cohort of investors sharing the same tier, jurisdiction and expiry.
+        #[ink(message)]
+        pub fn add_partitions(
+            &mut self,
+            partitions: Vec<AccountId>,
+            tier: u8,
+            jurisdiction: u16,
+            expires_at: u64,
+        ) {
+            for partition in partitions {
+                self.add_partition(partition, tier, jurisdiction, expires_at);
             }
         }
 
@@ -49,8 +134,128 @@ mod erc_1400 {
         pub fn revoke_partition(&mut self, parititon: AccountId) {
             let caller = self.env().caller();
             if caller == *self.owner {
-                self.partitions.insert(parititon, false);
+                self.partitions.take(&parititon);
+            }
+        }
+
+        #[ink(message)]
+        pub fn revoke_partitions(&mut self, partitions: Vec<AccountId>) {
+            for partition in partitions {
+                self.revoke_partition(partition);
+            }
+        }
+
+        #[ink(message)]
+        pub fn investor(&self, partition: AccountId) -> Option<Investor> {
+            self.partitions.get(&partition).copied()
+        }
+
+        ///Set the lowest tier allowed to transfer, only by owner.
+        #[ink(message)]
+        pub fn set_min_tier(&mut self, tier: u8) -> Result<()> {
+            if self.env().caller() != *self.owner {
+                return Err(Error::NotPermission);
+            }
+            *self.min_tier = tier;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn min_tier(&self) -> u8 {
+            *self.min_tier
+        }
+
+        ///Set identity registry consulted on transfer, None to disable, only by owner.
+        #[ink(message)]
+        pub fn set_identity_registry(&mut self, registry: Option<AccountId>) -> Result<()> {
+            if self.env().caller() != *self.owner {
+                return Err(Error::NotPermission);
             }
+            *self.identity_registry = registry;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn identity_registry(&self) -> Option<AccountId> {
+            *self.identity_registry
+        }
+
+        ///Add a compliance module, only by owner. The module must already be bound to this
+        ///token. It records transfers at once, is seeded with existing balances through
+        ///`seed_compliance_module` and checks transfers once `activate_compliance_module`
+        ///is called. A module can only be added once.
+        #[ink(message)]
+        pub fn add_compliance_module(&mut self, module: AccountId) -> Result<()> {
+            if self.env().caller() != *self.owner {
+                return Err(Error::NotPermission);
+            }
+            if self.added_modules.contains_key(&module) {
+                return Err(Error::ModuleAlreadyAdded);
+            }
+            if self.seeding_module.is_some() {
+                return Err(Error::SeedingInProgress);
+            }
+            if self.compliance_modules.len() >= MAX_COMPLIANCE_MODULES {
+                return Err(Error::TooManyModules);
+            }
+
+            self.added_modules.insert(module, true);
+            *self.seeding_module = Some(module);
+            Ok(())
+        }
+
+        ///Seed the module being added with the balances of up to `MAX_SEED_BATCH` holders,
+        ///only by owner. Seeding a holder again replaces its seeded balance.
+        #[ink(message)]
+        pub fn seed_compliance_module(&mut self, holders: Vec<AccountId>) -> Result<()> {
+            if self.env().caller() != *self.owner {
+                return Err(Error::NotPermission);
+            }
+            let module = self.seeding_module.ok_or(Error::NoModuleSeeding)?;
+            if holders.len() > MAX_SEED_BATCH {
+                return Err(Error::SeedBatchTooLarge);
+            }
+
+            let mut hooks: ComplianceModule = FromAccountId::from_account_id(module);
+            for holder in holders {
+                hooks
+                    .seed(holder, self.balance_of_or_zero(&holder))
+                    .map_err(|_| Error::ModuleRefused)?;
+            }
+            Ok(())
+        }
+
+        ///Start checking transfers against the seeded module, only by owner.
+        #[ink(message)]
+        pub fn activate_compliance_module(&mut self) -> Result<()> {
+            if self.env().caller() != *self.owner {
+                return Err(Error::NotPermission);
+            }
+            let module = self.seeding_module.take().ok_or(Error::NoModuleSeeding)?;
+            self.compliance_modules.push(module);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_compliance_module(&mut self, module: AccountId) -> Result<()> {
+            if self.env().caller() != *self.owner {
+                return Err(Error::NotPermission);
+            }
+            self.compliance_modules.retain(|m| *m != module);
+            if *self.seeding_module == Some(module) {
+                *self.seeding_module = None;
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn compliance_modules(&self) -> Vec<AccountId> {
+            self.compliance_modules.clone()
+        }
+
+        #[ink(message)]
+        pub fn seeding_module(&self) -> Option<AccountId> {
+            *self.seeding_module
         }
 
         #[ink(message)]
@@ -64,11 +269,29 @@ mod erc_1400 {
         #[ink(message)]
         pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
             let caller = self.env().caller();
-            self.is_permission(caller)?;
-            self.is_permission(to)?;
+            self.check_transfer(caller, to, value).map_err(|(_, reason)| reason)?;
+            self.notify_compliance(caller, to, value)?;
             self.transfer_from_to(caller, to, value)
         }
 
+        ///Check a transfer in the default partition without executing it, returns ERC-1066 status, reason and destination partition.
+        #[ink(message)]
+        pub fn can_transfer(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            partition: Hash,
+            value: Balance,
+        ) -> (u8, Option<Error>, Hash) {
+            if partition != Hash::from(DEFAULT_PARTITION) {
+                return (TRANSFER_FAILURE, Some(Error::UnknownPartition), partition);
+            }
+            match self.check_transfer(from, to, value) {
+                Ok(()) => (TRANSFER_SUCCESS, None, partition),
+                Err((status, reason)) => (status, Some(reason), partition),
+            }
+        }
+
         #[ink(message)]
         pub fn balance_of(&self, owner: AccountId) -> Balance {
             self.balance_of_or_zero(&owner)
@@ -109,17 +332,97 @@ mod erc_1400 {
             *self.balances.get(owner).unwrap_or(&0)
         }
 
-        fn partition_or_not(&self, partition: &AccountId) -> bool {
-            *self.partitions.get(partition).unwrap_or(&false)
+        fn check_transfer(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> core::result::Result<(), (u8, Error)> {
+            self.check_transfer_with(from, to, value, |from, to, value| {
+                self.compliance_allows(from, to, value)
+            })
+        }
+
+        /// `check_transfer` with the compliance module calls replaced by `compliant`.
+        fn check_transfer_with(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            compliant: impl Fn(AccountId, AccountId, Balance) -> bool,
+        ) -> core::result::Result<(), (u8, Error)> {
+            self.is_permission(from).map_err(|reason| (INVALID_SENDER, reason))?;
+            self.is_permission(to).map_err(|reason| (INVALID_RECEIVER, reason))?;
+            Self::verify_identities(from, to, |account| self.is_verified(account))?;
+            if self.balance_of_or_zero(&from) < value {
+                return Err((INSUFFICIENT_BALANCE, Error::InsufficientBalance));
+            }
+            if !compliant(from, to, value) {
+                return Err((TRANSFER_FAILURE, Error::ComplianceRejected));
+            }
+            Ok(())
+        }
+
+        fn compliance_allows(&self, from: AccountId, to: AccountId, value: Balance) -> bool {
+            self.compliance_modules.iter().all(|module| {
+                let module: ComplianceModule = FromAccountId::from_account_id(*module);
+                module.check_transfer(from, to, value)
+            })
+        }
+
+        /// Let every module, including the one being seeded, record a transfer before balances
+        /// move. When a module refuses, the modules that already recorded it are reverted.
+        fn notify_compliance(&self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
+            let modules = self.compliance_modules.iter().chain(self.seeding_module.iter());
+            for (notified, module) in modules.clone().enumerate() {
+                let mut hooks: ComplianceModule = FromAccountId::from_account_id(*module);
+                if hooks.transferred(from, to, value).is_err() {
+                    for module in modules.clone().take(notified) {
+                        let mut hooks: ComplianceModule = FromAccountId::from_account_id(*module);
+                        let _ = hooks.transferred(to, from, value);
+                    }
+                    return Err(Error::ModuleRefused);
+                }
+            }
+            Ok(())
+        }
+
+        /// Both sides of a transfer must pass `verified`, the sender is reported first.
+        fn verify_identities(
+            from: AccountId,
+            to: AccountId,
+            verified: impl Fn(AccountId) -> bool,
+        ) -> core::result::Result<(), (u8, Error)> {
+            if !verified(from) {
+                return Err((INVALID_SENDER, Error::IdentityNotVerified));
+            }
+            if !verified(to) {
+                return Err((INVALID_RECEIVER, Error::IdentityNotVerified));
+            }
+            Ok(())
+        }
+
+        fn is_verified(&self, account: AccountId) -> bool {
+            match *self.identity_registry {
+                Some(registry) if account != *self.owner => {
+                    let registry: IdentityRegistry = FromAccountId::from_account_id(registry);
+                    registry.is_verified(account)
+                }
+                _ => true,
+            }
         }
 
         fn is_permission(&self, partition: AccountId) -> Result<()> {
             if partition == *self.owner {
-                Ok(())
-            } else if self.partition_or_not(&partition) {
-                Ok(())
+                return Ok(());
+            }
+            let investor = self.investor(partition).ok_or(Error::NotPermission)?;
+            if self.env().block_timestamp() > investor.expires_at {
+                Err(Error::InvestorExpired)
+            } else if investor.tier < *self.min_tier {
+                Err(Error::TierTooLow)
             } else {
-                Err(Error::NotPermission)
+                Ok(())
             }
         }
     }
@@ -139,7 +442,7 @@ mod erc_1400 {
         #[ink::test]
         fn add_partition_work() {
             let mut contract = Erc1400::new(100);
-            contract.add_partition(AccountId::from([0x0; 32]));
+            contract.add_partition(AccountId::from([0x0; 32]), 1, 840, u64::MAX);
             assert_eq!(contract.is_partition(AccountId::from([0x0; 32])), true);
             assert_eq!(contract.is_partition(AccountId::from([0x1; 32])), true)
         }
@@ -147,7 +450,7 @@ mod erc_1400 {
         #[ink::test]
         fn revoke_partition_work() {
             let mut contract = Erc1400::new(100);
-            contract.add_partition(AccountId::from([0x0; 32]));
+            contract.add_partition(AccountId::from([0x0; 32]), 1, 840, u64::MAX);
             assert_eq!(contract.is_partition(AccountId::from([0x0; 32])), true);
             contract.revoke_partition(AccountId::from([0x0; 32]));
             assert_eq!(contract.is_partition(AccountId::from([0x0; 32])), false)
@@ -156,13 +459,155 @@ mod erc_1400 {
         #[ink::test]
         fn transfer_works() {
             let mut contract = Erc1400::new(100);
-            contract.add_partition(AccountId::from([0x0; 32]));
+            contract.add_partition(AccountId::from([0x0; 32]), 1, 840, u64::MAX);
             assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
             assert_eq!(contract.transfer(AccountId::from([0x0; 32]), 10), Ok(()));
             assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 10);
             assert_ne!(contract.transfer(AccountId::from([0x0; 32]), 100), Ok(()));
         }
 
+        #[ink::test]
+        fn investor_expiry_and_tier_works() {
+            let mut contract = Erc1400::new(100);
+            let investor = AccountId::from([0x0; 32]);
+            contract.add_partition(investor, 1, 840, 0);
+            assert_eq!(
+                contract.investor(investor),
+                Some(Investor { tier: 1, jurisdiction: 840, expires_at: 0 })
+            );
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().unwrap();
+            assert_eq!(contract.transfer(investor, 10), Err(Error::InvestorExpired));
+
+            contract.add_partition(investor, 1, 840, u64::MAX);
+            assert_eq!(contract.set_min_tier(2), Ok(()));
+            assert_eq!(contract.transfer(investor, 10), Err(Error::TierTooLow));
+            assert_eq!(contract.set_min_tier(1), Ok(()));
+            assert_eq!(contract.transfer(investor, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn batch_partition_works() {
+            let mut contract = Erc1400::new(100);
+            let cohort = vec![AccountId::from([0x2; 32]), AccountId::from([0x3; 32])];
+            contract.add_partitions(cohort.clone(), 1, 840, u64::MAX);
+            assert!(cohort.iter().all(|investor| contract.is_partition(*investor)));
+            contract.revoke_partitions(cohort.clone());
+            assert!(cohort.iter().all(|investor| !contract.is_partition(*investor)));
+        }
+
+        #[ink::test]
+        fn can_transfer_works() {
+            let mut contract = Erc1400::new(100);
+            let owner = AccountId::from([0x1; 32]);
+            let investor = AccountId::from([0x0; 32]);
+            let partition = Hash::from(DEFAULT_PARTITION);
+            assert_eq!(
+                contract.can_transfer(owner, investor, Hash::from([0x7; 32]), 10),
+                (TRANSFER_FAILURE, Some(Error::UnknownPartition), Hash::from([0x7; 32]))
+            );
+            assert_eq!(
+                contract.can_transfer(owner, investor, partition, 10),
+                (INVALID_RECEIVER, Some(Error::NotPermission), partition)
+            );
+            assert_eq!(contract.transfer(investor, 10), Err(Error::NotPermission));
+
+            contract.add_partition(investor, 1, 840, u64::MAX);
+            assert_eq!(
+                contract.can_transfer(owner, investor, partition, 101),
+                (INSUFFICIENT_BALANCE, Some(Error::InsufficientBalance), partition)
+            );
+            assert_eq!(
+                contract.can_transfer(owner, investor, partition, 10),
+                (TRANSFER_SUCCESS, None, partition)
+            );
+        }
+
+        #[ink::test]
+        fn verify_identities_works() {
+            let sender = AccountId::from([0x2; 32]);
+            let receiver = AccountId::from([0x3; 32]);
+            assert_eq!(
+                Erc1400::verify_identities(sender, receiver, |account| account == receiver),
+                Err((INVALID_SENDER, Error::IdentityNotVerified))
+            );
+            assert_eq!(
+                Erc1400::verify_identities(sender, receiver, |account| account == sender),
+                Err((INVALID_RECEIVER, Error::IdentityNotVerified))
+            );
+            assert_eq!(Erc1400::verify_identities(sender, receiver, |_| true), Ok(()));
+        }
+
+        #[ink::test]
+        fn set_identity_registry_works() {
+            let mut contract = Erc1400::new(100);
+            let registry = AccountId::from([0x9; 32]);
+            assert_eq!(contract.set_identity_registry(Some(registry)), Ok(()));
+            assert_eq!(contract.identity_registry(), Some(registry));
+
+            assert!(contract.transfer_ownership(AccountId::from([0x0; 32])).is_ok());
+            assert_eq!(contract.set_identity_registry(None), Err(Error::NotPermission));
+        }
+
+        #[ink::test]
+        fn compliance_module_list_works() {
+            let mut contract = Erc1400::new(0);
+            let module = AccountId::from([0x9; 32]);
+            assert_eq!(contract.seed_compliance_module(Vec::new()), Err(Error::NoModuleSeeding));
+            assert_eq!(contract.add_compliance_module(module), Ok(()));
+            assert_eq!(contract.seeding_module(), Some(module));
+            assert!(contract.compliance_modules().is_empty());
+            assert_eq!(
+                contract.add_compliance_module(AccountId::from([0xa; 32])),
+                Err(Error::SeedingInProgress)
+            );
+            assert_eq!(
+                contract.seed_compliance_module(vec![module; MAX_SEED_BATCH + 1]),
+                Err(Error::SeedBatchTooLarge)
+            );
+            assert_eq!(contract.seed_compliance_module(Vec::new()), Ok(()));
+            assert_eq!(contract.activate_compliance_module(), Ok(()));
+            assert_eq!(contract.seeding_module(), None);
+            assert_eq!(contract.compliance_modules(), vec![module]);
+
+            // A module is seeded once, adding it again would count every balance twice.
+            assert_eq!(contract.add_compliance_module(module), Err(Error::ModuleAlreadyAdded));
+            assert_eq!(contract.remove_compliance_module(module), Ok(()));
+            assert!(contract.compliance_modules().is_empty());
+            assert_eq!(contract.add_compliance_module(module), Err(Error::ModuleAlreadyAdded));
+
+            for i in 0..MAX_COMPLIANCE_MODULES {
+                assert_eq!(contract.add_compliance_module(AccountId::from([i as u8; 32])), Ok(()));
+                assert_eq!(contract.activate_compliance_module(), Ok(()));
+            }
+            assert_eq!(
+                contract.add_compliance_module(AccountId::from([0xa; 32])),
+                Err(Error::TooManyModules)
+            );
+
+            assert!(contract.transfer_ownership(AccountId::from([0x0; 32])).is_ok());
+            assert_eq!(
+                contract.add_compliance_module(AccountId::from([0xa; 32])),
+                Err(Error::NotPermission)
+            );
+        }
+
+        #[ink::test]
+        fn compliance_rejection_works() {
+            let contract = Erc1400::new(100);
+            let owner = AccountId::from([0x1; 32]);
+            let investor = AccountId::from([0x0; 32]);
+            assert_eq!(
+                contract.check_transfer_with(owner, owner, 10, |_, _, _| false),
+                Err((TRANSFER_FAILURE, Error::ComplianceRejected))
+            );
+            assert_eq!(contract.check_transfer_with(owner, owner, 10, |_, _, _| true), Ok(()));
+            assert_eq!(
+                contract.check_transfer_with(owner, investor, 10, |_, _, _| false),
+                Err((INVALID_RECEIVER, Error::NotPermission))
+            );
+        }
+
         #[ink::test]
         fn transfer_ownership_works() {
             let mut contract = Erc1400::new(777);
@@ -1,12 +1,21 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use ink_lang as ink;
+pub mod models;
 
 #[ink::contract]
 mod escrow {
+    use super::*;
+    use models::{doc::*, order::*};
 
+    use ink_prelude::{string::String, vec::Vec};
     use ink_storage::{collections::HashMap as StorageHashMap, lazy::Lazy};
 
+    /// Maximum number of documents attached to one order.
+    const MAX_EVIDENCES: usize = 16;
+    /// Maximum length of an evidence document uri.
+    const MAX_URI_LEN: usize = 256;
+
     #[derive(Debug, PartialEq, Eq, scale::Encode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub enum Error {
@@ -14,6 +23,12 @@ mod escrow {
         InsufficientAllowance,
         NoPermission,
         OnlyOwner,
+        OrderNotFound,
+        OrderNotAccepted,
+        OrderAlreadyAccepted,
+        OrderClosed,
+        TooManyEvidences,
+        UriTooLong,
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
@@ -25,7 +40,10 @@ mod escrow {
         total_supply: Lazy<Balance>,
         balances: StorageHashMap<AccountId, Balance>,
         allowances: StorageHashMap<(AccountId, AccountId), Balance>,
-        escrow_balances: StorageHashMap<(AccountId, AccountId), Balance>,
+        orders: StorageHashMap<u64, Order>,
+        next_order_id: Lazy<u64>,
+        /// Dispute documents of each order, kept after the order settles.
+        evidences: StorageHashMap<u64, Vec<Evidence>>,
     }
 
     #[ink(event)]
@@ -56,6 +74,17 @@ mod escrow {
         to: AccountId,
     }
 
+    #[ink(event)]
+    pub struct OrderCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        buyer: AccountId,
+        #[ink(topic)]
+        seller: AccountId,
+        value: Balance,
+    }
+
     impl Escrow {
         #[ink(constructor)]
         pub fn new(initial_supply: Balance) -> Self {
@@ -74,66 +103,222 @@ mod escrow {
                 owner: Lazy::new(caller),
                 total_supply: Lazy::new(initial_supply),
                 allowances: StorageHashMap::new(),
-                escrow_balances: StorageHashMap::new(),
+                orders: StorageHashMap::new(),
+                next_order_id: Lazy::new(0),
+                evidences: StorageHashMap::new(),
             }
         }
         // ----------------------------------------------------------------------------------------------------------------
+        /// Lock `value` of the caller's balance in a new order and return its id.
+        /// A buyer may keep several open orders with the same seller.
         #[ink(message)]
-        pub fn create_payment(&mut self, seller: AccountId, value: Balance) -> Result<()> {
-            let order = self.env().caller();
-
-            let buyer_balance = self.balance_of_or_zero(&order);
+        pub fn create_payment(&mut self, seller: AccountId, value: Balance) -> Result<u64> {
+            let buyer = self.env().caller();
+            let buyer_balance = self.balance_of_or_zero(&buyer);
             if buyer_balance < value {
                 return Err(Error::InsufficientBalance);
             }
-            self.balances.insert(order, buyer_balance - value);
+            self.balances.insert(buyer, buyer_balance - value);
+
+            let id = *self.next_order_id;
+            *self.next_order_id += 1;
+            self.orders.insert(
+                id,
+                Order {
+                    buyer,
+                    seller,
+                    amount: value,
+                    accepted: false,
+                    oracle: None,
+                    status: OrderStatus::Open,
+                },
+            );
+            self.env().emit_event(OrderCreated { id, buyer, seller, value });
+            Ok(id)
+        }
 
-            let escrow_balance = self.escrow_of_or_zero(&order, &seller);
-            self.escrow_balances
-                .insert((order, seller), escrow_balance + value);
+        /// Seller agrees to the order, after which the buyer can no longer cancel it.
+        #[ink(message)]
+        pub fn accept_order(&mut self, id: u64) -> Result<()> {
+            let seller = self.env().caller();
+            let mut order = self.open_order(id)?;
+            if order.seller != seller {
+                return Err(Error::NoPermission);
+            }
+            if order.accepted {
+                return Err(Error::OrderAlreadyAccepted);
+            }
+            order.accepted = true;
+            self.orders.insert(id, order);
             Ok(())
         }
 
+        /// Buyer names the account whose delivery confirmation may release the order.
+        /// It can only be set before the seller accepts, so the seller agrees to it.
         #[ink(message)]
-        pub fn complete_payment(&mut self, from: AccountId, to: AccountId) -> Result<()> {
+        pub fn set_oracle(&mut self, id: u64, oracle: AccountId) -> Result<()> {
+            let buyer = self.env().caller();
+            let mut order = self.open_order(id)?;
+            if order.buyer != buyer {
+                return Err(Error::NoPermission);
+            }
+            if order.accepted {
+                return Err(Error::OrderAlreadyAccepted);
+            }
+            order.oracle = Some(oracle);
+            self.orders.insert(id, order);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn oracle_of(&self, id: u64) -> Option<AccountId> {
+            self.orders.get(&id).and_then(|order| order.oracle)
+        }
+
+        #[ink(message)]
+        pub fn order(&self, id: u64) -> Option<Order> {
+            self.orders.get(&id).copied()
+        }
+
+        /// Buyer or seller attaches a dispute document such as an invoice, photo or chat log.
+        #[ink(message)]
+        pub fn submit_evidence(
+            &mut self,
+            id: u64,
+            document_uri: String,
+            document_hash: Hash,
+        ) -> Result<()> {
             let caller = self.env().caller();
-            let esbalance = self.escrow_of_or_zero(&from, &to);
+            let order = self.open_order(id)?;
+            if caller != order.buyer && caller != order.seller {
+                return Err(Error::NoPermission);
+            }
+            if document_uri.len() > MAX_URI_LEN {
+                return Err(Error::UriTooLong);
+            }
+            let mut evidences = self.evidence_of(id);
+            if evidences.len() >= MAX_EVIDENCES {
+                return Err(Error::TooManyEvidences);
+            }
 
-            if caller.clone() == from || caller.clone() == *self.owner {
-                let balance = self.balance_of_or_zero(&to);
-                self.balances.insert(to, esbalance + balance);
-                self.escrow_balances.insert((from, to), 0);
+            evidences.push(Evidence {
+                submitter: caller,
+                document: Document { doc_uri: document_uri, doc_hash: document_hash },
+                timestamp: self.env().block_timestamp(),
+            });
+            self.evidences.insert(id, evidences);
+            Ok(())
+        }
 
-                Ok(())
-            } else {
-                Err(Error::NoPermission)
+        #[ink(message)]
+        pub fn evidence_of(&self, id: u64) -> Vec<Evidence> {
+            match self.evidences.get(&id) {
+                None => Vec::new(),
+                Some(e) => e.clone(),
+            }
+        }
+
+        /// Buyer backs out of an order the seller has not accepted yet and is refunded at once.
+        #[ink(message)]
+        pub fn cancel_order(&mut self, id: u64) -> Result<()> {
+            let buyer = self.env().caller();
+            let order = self.open_order(id)?;
+            if order.buyer != buyer {
+                return Err(Error::NoPermission);
             }
+            if order.accepted {
+                return Err(Error::OrderAlreadyAccepted);
+            }
+            self.close_order(id, order, order.buyer, OrderStatus::Cancelled);
+            Ok(())
+        }
+
+        /// Release an accepted order to the seller. The buyer, the owner or the order's
+        /// oracle may call it; a call signed by the oracle is its delivery attestation.
+        #[ink(message)]
+        pub fn complete_payment(&mut self, id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            self.complete_order(caller, id)
+        }
+
+        #[ink(message)]
+        pub fn refund(&mut self, id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            self.refund_order(caller, id)
+        }
+
+        /// Complete many orders at once. Each entry is checked on its own and reported
+        /// in the same position of the result, so one failure does not abort the rest
+        /// of the batch.
+        #[ink(message)]
+        pub fn batch_complete(&mut self, ids: Vec<u64>) -> Vec<Result<()>> {
+            let caller = self.env().caller();
+            ids.into_iter()
+                .map(|id| self.complete_order(caller, id))
+                .collect()
         }
 
+        /// Refund many orders at once, reporting each entry like `batch_complete`.
         #[ink(message)]
-        pub fn refund(&mut self, from: AccountId, to: AccountId) -> Result<()> {
+        pub fn batch_refund(&mut self, ids: Vec<u64>) -> Vec<Result<()>> {
             let caller = self.env().caller();
-            let esbalance = self.escrow_of_or_zero(&from, &to);
+            ids.into_iter()
+                .map(|id| self.refund_order(caller, id))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn is_order_accepted(&self, id: u64) -> bool {
+            self.orders.get(&id).map_or(false, |order| order.accepted)
+        }
 
-            if caller.clone() == to || caller.clone() == *self.owner {
-                let balance = self.balance_of_or_zero(&from);
-                self.balances.insert(from, esbalance + balance);
+        /// Value still held by an order, zero once it settled.
+        #[ink(message)]
+        pub fn escrow_balance(&self, id: u64) -> Balance {
+            match self.orders.get(&id) {
+                Some(order) if order.status == OrderStatus::Open => order.amount,
+                _ => 0,
+            }
+        }
 
-                self.escrow_balances.insert((from, to), 0);
+        fn open_order(&self, id: u64) -> Result<Order> {
+            match self.orders.get(&id) {
+                None => Err(Error::OrderNotFound),
+                Some(order) if order.status != OrderStatus::Open => Err(Error::OrderClosed),
+                Some(order) => Ok(*order),
+            }
+        }
 
+        fn close_order(&mut self, id: u64, mut order: Order, payee: AccountId, status: OrderStatus) {
+            let balance = self.balance_of_or_zero(&payee);
+            self.balances.insert(payee, balance + order.amount);
+            order.status = status;
+            self.orders.insert(id, order);
+        }
+
+        fn complete_order(&mut self, caller: AccountId, id: u64) -> Result<()> {
+            let order = self.open_order(id)?;
+
+            if caller == order.buyer || caller == *self.owner || order.oracle == Some(caller) {
+                if !order.accepted {
+                    return Err(Error::OrderNotAccepted);
+                }
+                self.close_order(id, order, order.seller, OrderStatus::Completed);
                 Ok(())
             } else {
                 Err(Error::NoPermission)
             }
         }
 
-        #[ink(message)]
-        pub fn escrow_balance(&self, from: AccountId, to: AccountId) -> Balance {
-            self.escrow_of_or_zero(&from, &to)
-        }
+        fn refund_order(&mut self, caller: AccountId, id: u64) -> Result<()> {
+            let order = self.open_order(id)?;
 
-        fn escrow_of_or_zero(&self, order: &AccountId, seller: &AccountId) -> Balance {
-            *self.escrow_balances.get(&(*order, *seller)).unwrap_or(&0)
+            if caller == order.seller || caller == *self.owner {
+                self.close_order(id, order, order.buyer, OrderStatus::Refunded);
+                Ok(())
+            } else {
+                Err(Error::NoPermission)
+            }
         }
         // ----------------------------------------------------------------------------------------------------------------
         #[ink(message)]
@@ -248,6 +433,17 @@ mod escrow {
 
         use ink_lang as ink;
 
+        fn set_caller(caller: AccountId) {
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            let mut data =
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4]));
+            data.push_arg(&caller);
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller, callee, 1000000, 1000000, data,
+            );
+        }
+
         #[ink::test]
         fn new_works() {
             let contract = Escrow::new(777);
@@ -268,9 +464,26 @@ mod escrow {
             let buyer = AccountId::from([0x1; 32]);
             let seller = AccountId::from([0x0; 32]);
             assert_eq!(contract.balance_of(buyer), 100);
-            assert_eq!(contract.create_payment(seller, 30), Ok(()));
+            assert_eq!(contract.create_payment(seller, 30), Ok(0));
+            assert_eq!(contract.balance_of(buyer), 70);
+            assert_eq!(contract.escrow_balance(0), 30);
+        }
+
+        #[ink::test]
+        fn several_orders_per_pair_work() {
+            let mut contract = Escrow::new(100);
+            let buyer = AccountId::from([0x1; 32]);
+            let seller = AccountId::from([0x0; 32]);
+            assert_eq!(contract.create_payment(seller, 30), Ok(0));
+            set_caller(seller);
+            assert_eq!(contract.accept_order(0), Ok(()));
+            set_caller(buyer);
+            assert_eq!(contract.create_payment(seller, 20), Ok(1));
+            assert_eq!(contract.is_order_accepted(0), true);
+            assert_eq!(contract.is_order_accepted(1), false);
+            assert_eq!(contract.cancel_order(1), Ok(()));
+            assert_eq!(contract.escrow_balance(0), 30);
             assert_eq!(contract.balance_of(buyer), 70);
-            assert_eq!(contract.escrow_balance(buyer, seller), 30);
         }
 
         #[ink::test]
@@ -278,24 +491,189 @@ mod escrow {
             let mut contract = Escrow::new(100);
             let buyer = AccountId::from([0x1; 32]);
             let seller = AccountId::from([0x0; 32]);
-            assert_eq!(contract.create_payment(seller, 30), Ok(()));
+            assert_eq!(contract.create_payment(seller, 30), Ok(0));
             assert_eq!(contract.balance_of(seller), 0);
-            assert_eq!(contract.complete_payment(buyer, seller), Ok(()));
+            assert_eq!(contract.complete_payment(0), Err(Error::OrderNotAccepted));
+            set_caller(seller);
+            assert_eq!(contract.accept_order(0), Ok(()));
+            set_caller(buyer);
+            assert_eq!(contract.complete_payment(0), Ok(()));
             assert_eq!(contract.balance_of(seller), 30);
-            assert_eq!(contract.create_payment(seller, 30), Ok(()));
-            assert_eq!(contract.complete_payment(buyer, seller), Ok(()));
+            assert_eq!(contract.complete_payment(0), Err(Error::OrderClosed));
+            assert_eq!(contract.order(0).unwrap().status, OrderStatus::Completed);
+            assert_eq!(contract.create_payment(seller, 30), Ok(1));
+            set_caller(seller);
+            assert_eq!(contract.accept_order(1), Ok(()));
+            set_caller(buyer);
+            assert_eq!(contract.complete_payment(1), Ok(()));
             assert_eq!(contract.balance_of(seller), 60);
         }
 
+        #[ink::test]
+        fn accept_order_works() {
+            let mut contract = Escrow::new(100);
+            let buyer = AccountId::from([0x1; 32]);
+            let seller = AccountId::from([0x0; 32]);
+            set_caller(seller);
+            assert_eq!(contract.accept_order(0), Err(Error::OrderNotFound));
+            set_caller(buyer);
+            assert_eq!(contract.create_payment(seller, 30), Ok(0));
+            assert_eq!(contract.accept_order(0), Err(Error::NoPermission));
+            set_caller(seller);
+            assert_eq!(contract.accept_order(0), Ok(()));
+            assert_eq!(contract.is_order_accepted(0), true);
+            assert_eq!(contract.accept_order(0), Err(Error::OrderAlreadyAccepted));
+        }
+
+        #[ink::test]
+        fn cancel_order_works() {
+            let mut contract = Escrow::new(100);
+            let buyer = AccountId::from([0x1; 32]);
+            let seller = AccountId::from([0x0; 32]);
+            assert_eq!(contract.cancel_order(0), Err(Error::OrderNotFound));
+            assert_eq!(contract.create_payment(seller, 30), Ok(0));
+            assert_eq!(contract.cancel_order(0), Ok(()));
+            assert_eq!(contract.balance_of(buyer), 100);
+            assert_eq!(contract.escrow_balance(0), 0);
+            assert_eq!(contract.cancel_order(0), Err(Error::OrderClosed));
+
+            assert_eq!(contract.create_payment(seller, 30), Ok(1));
+            set_caller(seller);
+            assert_eq!(contract.accept_order(1), Ok(()));
+            set_caller(buyer);
+            assert_eq!(contract.cancel_order(1), Err(Error::OrderAlreadyAccepted));
+            assert_eq!(contract.balance_of(buyer), 70);
+        }
+
+        #[ink::test]
+        fn oracle_complete_payment_works() {
+            let mut contract = Escrow::new(100);
+            let buyer = AccountId::from([0x1; 32]);
+            let seller = AccountId::from([0x0; 32]);
+            let oracle = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_oracle(0, oracle), Err(Error::OrderNotFound));
+            assert_eq!(contract.create_payment(seller, 30), Ok(0));
+            assert_eq!(contract.set_oracle(0, oracle), Ok(()));
+            assert_eq!(contract.oracle_of(0), Some(oracle));
+
+            set_caller(seller);
+            assert_eq!(contract.accept_order(0), Ok(()));
+            assert_eq!(contract.complete_payment(0), Err(Error::NoPermission));
+            set_caller(buyer);
+            assert_eq!(contract.set_oracle(0, seller), Err(Error::OrderAlreadyAccepted));
+
+            set_caller(oracle);
+            assert_eq!(contract.complete_payment(0), Ok(()));
+            assert_eq!(contract.balance_of(seller), 30);
+        }
+
+        #[ink::test]
+        fn submit_evidence_works() {
+            let mut contract = Escrow::new(100);
+            let buyer = AccountId::from([0x1; 32]);
+            let seller = AccountId::from([0x0; 32]);
+            let doc_hash = Hash::from([0x5; 32]);
+            assert_eq!(
+                contract.submit_evidence(0, "ipfs://invoice".to_string(), doc_hash),
+                Err(Error::OrderNotFound)
+            );
+            assert_eq!(contract.create_payment(seller, 30), Ok(0));
+            assert_eq!(
+                contract.submit_evidence(0, "ipfs://invoice".to_string(), doc_hash),
+                Ok(())
+            );
+            set_caller(seller);
+            assert_eq!(
+                contract.submit_evidence(0, "ipfs://photo".to_string(), doc_hash),
+                Ok(())
+            );
+            set_caller(AccountId::from([0x3; 32]));
+            assert_eq!(
+                contract.submit_evidence(0, "ipfs://other".to_string(), doc_hash),
+                Err(Error::NoPermission)
+            );
+
+            let evidences = contract.evidence_of(0);
+            assert_eq!(evidences.len(), 2);
+            assert_eq!(evidences[0].submitter, buyer);
+            assert_eq!(evidences[1].submitter, seller);
+            assert_eq!(evidences[1].document.doc_uri, "ipfs://photo".to_string());
+
+            set_caller(seller);
+            assert_eq!(contract.accept_order(0), Ok(()));
+            set_caller(buyer);
+            assert_eq!(contract.complete_payment(0), Ok(()));
+            assert_eq!(contract.evidence_of(0).len(), 2);
+            assert_eq!(
+                contract.submit_evidence(0, "ipfs://late".to_string(), doc_hash),
+                Err(Error::OrderClosed)
+            );
+        }
+
+        #[ink::test]
+        fn evidence_is_bounded() {
+            let mut contract = Escrow::new(100);
+            let seller = AccountId::from([0x0; 32]);
+            let doc_hash = Hash::from([0x5; 32]);
+            assert_eq!(contract.create_payment(seller, 30), Ok(0));
+            assert_eq!(
+                contract.submit_evidence(0, "x".repeat(MAX_URI_LEN + 1), doc_hash),
+                Err(Error::UriTooLong)
+            );
+            for _ in 0..MAX_EVIDENCES {
+                assert_eq!(contract.submit_evidence(0, "ipfs://doc".to_string(), doc_hash), Ok(()));
+            }
+            assert_eq!(
+                contract.submit_evidence(0, "ipfs://doc".to_string(), doc_hash),
+                Err(Error::TooManyEvidences)
+            );
+        }
+
+        #[ink::test]
+        fn batch_complete_works() {
+            let mut contract = Escrow::new(100);
+            let buyer = AccountId::from([0x1; 32]);
+            let seller_a = AccountId::from([0x0; 32]);
+            let seller_b = AccountId::from([0x2; 32]);
+            assert_eq!(contract.create_payment(seller_a, 30), Ok(0));
+            assert_eq!(contract.create_payment(seller_b, 20), Ok(1));
+            set_caller(seller_a);
+            assert_eq!(contract.accept_order(0), Ok(()));
+            set_caller(buyer);
+
+            let results = contract.batch_complete(vec![0, 1]);
+            assert_eq!(results, vec![Ok(()), Err(Error::OrderNotAccepted)]);
+            assert_eq!(contract.balance_of(seller_a), 30);
+            assert_eq!(contract.escrow_balance(1), 20);
+        }
+
+        #[ink::test]
+        fn batch_refund_works() {
+            let mut contract = Escrow::new(100);
+            let buyer = AccountId::from([0x1; 32]);
+            let seller_a = AccountId::from([0x0; 32]);
+            let seller_b = AccountId::from([0x2; 32]);
+            assert_eq!(contract.create_payment(seller_a, 30), Ok(0));
+            assert_eq!(contract.create_payment(seller_b, 20), Ok(1));
+            assert_eq!(contract.transfer_ownership(seller_a), Ok(()));
+
+            set_caller(seller_b);
+            let results = contract.batch_refund(vec![0, 1]);
+            assert_eq!(results, vec![Err(Error::NoPermission), Ok(())]);
+            assert_eq!(contract.balance_of(buyer), 70);
+            assert_eq!(contract.escrow_balance(0), 30);
+        }
+
         #[ink::test]
         fn refund_work() {
             let mut contract = Escrow::new(100);
             let buyer = AccountId::from([0x1; 32]);
             let seller = AccountId::from([0x0; 32]);
-            assert_eq!(contract.create_payment(seller, 30), Ok(()));
+            assert_eq!(contract.create_payment(seller, 30), Ok(0));
             assert_eq!(contract.balance_of(buyer), 70);
-            assert_eq!(contract.refund(buyer, seller), Ok(()));
+            assert_eq!(contract.refund(0), Ok(()));
             assert_eq!(contract.balance_of(buyer), 100);
+            assert_eq!(contract.refund(0), Err(Error::OrderClosed));
         }
 
         #[ink::test]
@@ -0,0 +1,11 @@
+use ink_env::AccountId;
+use ink_storage::traits::{PackedLayout, SpreadLayout};
+pub use document::Document;
+
+#[derive(Debug, scale::Encode, PackedLayout, scale::Decode, SpreadLayout, Clone)]
+#[cfg_attr( feature = "std", derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout))]
+pub struct Evidence {
+    pub submitter: AccountId,
+    pub document: Document,
+    pub timestamp: u64,
+}
@@ -0,0 +1,26 @@
+use ink_env::{AccountId, DefaultEnvironment, Environment};
+use ink_storage::traits::{PackedLayout, SpreadLayout};
+
+type Balance = <DefaultEnvironment as Environment>::Balance;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, scale::Encode, PackedLayout, scale::Decode, SpreadLayout)]
+#[cfg_attr( feature = "std", derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout))]
+pub enum OrderStatus {
+    Open,
+    Completed,
+    Refunded,
+    Cancelled,
+}
+
+/// A payment held in escrow. Settled orders stay in storage so their evidence
+/// remains readable under the same id.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, scale::Encode, PackedLayout, scale::Decode, SpreadLayout)]
+#[cfg_attr( feature = "std", derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout))]
+pub struct Order {
+    pub buyer: AccountId,
+    pub seller: AccountId,
+    pub amount: Balance,
+    pub accepted: bool,
+    pub oracle: Option<AccountId>,
+    pub status: OrderStatus,
+}
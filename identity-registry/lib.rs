@@ -0,0 +1,434 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+pub use self::identity_registry::{IdentityRegistry, IdentityVerifier};
+
+#[ink::contract]
+mod identity_registry {
+
+    use ink_prelude::{vec, vec::Vec};
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        lazy::Lazy,
+        traits::{PackedLayout, SpreadLayout},
+    };
+
+    /// Well-known claim topics.
+    pub const KYC: u32 = 1;
+    pub const ACCREDITATION: u32 = 2;
+    /// Most topics a topic list may hold, bounds the work done by `is_verified`.
+    pub const MAX_TOPICS: usize = 16;
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        OnlyOwner,
+        NotAgent,
+        NotTrustedIssuer,
+        IdentityNotFound,
+        ClaimNotFound,
+        TooManyTopics,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Identity {
+        /// ISO 3166-1 numeric country code.
+        pub country: u16,
+        pub registered_at: u64,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Claim {
+        /// Trusted issuer that signed the claim by submitting it.
+        pub issuer: AccountId,
+        /// Hash of the off-chain evidence backing the claim.
+        pub data: Hash,
+        pub issued_at: u64,
+        /// Time after which the claim no longer counts.
+        pub expires_at: u64,
+    }
+
+    /// Read access to investor verification for security tokens.
+    #[ink::trait_definition]
+    pub trait IdentityVerifier {
+        /// Whether `account` has an identity holding a valid claim for every required topic.
+        #[ink(message)]
+        fn is_verified(&self, account: AccountId) -> bool;
+
+        /// Country of the identity behind `account`, or `None` when it is not registered.
+        #[ink(message)]
+        fn investor_country(&self, account: AccountId) -> Option<u16>;
+    }
+
+    #[ink(storage)]
+    pub struct IdentityRegistry {
+        ///Owner of Contract.
+        owner: Lazy<AccountId>,
+        /// Accounts allowed to register and remove identities.
+        agents: StorageHashMap<AccountId, bool>,
+        /// Identities are keyed by wallet, an investor with several wallets is
+        /// registered and claimed once per wallet.
+        identities: StorageHashMap<AccountId, Identity>,
+        /// Topics each trusted issuer may issue claims for.
+        trusted_issuers: StorageHashMap<AccountId, Vec<u32>>,
+        /// Latest claim of each identity per topic.
+        claims: StorageHashMap<(AccountId, u32), Claim>,
+        /// Topics an identity needs a valid claim for to be verified.
+        required_topics: Vec<u32>,
+    }
+
+    #[ink(event)]
+    pub struct AgentUpdated {
+        #[ink(topic)]
+        agent: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct RequiredTopicsUpdated {
+        topics: Vec<u32>,
+    }
+
+    #[ink(event)]
+    pub struct IdentityRegistered {
+        #[ink(topic)]
+        account: AccountId,
+        country: u16,
+    }
+
+    #[ink(event)]
+    pub struct IdentityRemoved {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct TrustedIssuerUpdated {
+        #[ink(topic)]
+        issuer: AccountId,
+        topics: Vec<u32>,
+    }
+
+    #[ink(event)]
+    pub struct ClaimAdded {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        topic: u32,
+        #[ink(topic)]
+        issuer: AccountId,
+        expires_at: u64,
+    }
+
+    #[ink(event)]
+    pub struct ClaimRevoked {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        topic: u32,
+    }
+
+    impl IdentityVerifier for IdentityRegistry {
+        #[ink(message)]
+        fn is_verified(&self, account: AccountId) -> bool {
+            if !self.identities.contains_key(&account) {
+                return false;
+            }
+            let now = self.env().block_timestamp();
+            self.required_topics.iter().all(|topic| {
+                match self.claims.get(&(account, *topic)) {
+                    Some(claim) => now <= claim.expires_at && self.is_trusted_for(claim.issuer, *topic),
+                    None => false,
+                }
+            })
+        }
+
+        #[ink(message)]
+        fn investor_country(&self, account: AccountId) -> Option<u16> {
+            self.identities.get(&account).map(|identity| identity.country)
+        }
+    }
+
+    impl IdentityRegistry {
+        /// Every investor must hold a KYC claim until the owner sets other topics
+        /// with `set_required_topics`.
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            let caller = Self::env().caller();
+            let mut agents = StorageHashMap::new();
+            agents.insert(caller, true);
+            Self {
+                owner: Lazy::new(caller),
+                agents,
+                identities: StorageHashMap::new(),
+                trusted_issuers: StorageHashMap::new(),
+                claims: StorageHashMap::new(),
+                required_topics: vec![KYC],
+            }
+        }
+
+        #[ink(message)]
+        pub fn set_agent(&mut self, agent: AccountId, allowed: bool) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.agents.insert(agent, allowed);
+
+            self.env().emit_event(AgentUpdated { agent, allowed });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_agent(&self, account: AccountId) -> bool {
+            *self.agents.get(&account).unwrap_or(&false)
+        }
+
+        ///Register the identity of an investor wallet, only by agent. Each wallet of an
+        ///investor is registered, and holds its claims, on its own.
+        #[ink(message)]
+        pub fn register_identity(&mut self, account: AccountId, country: u16) -> Result<()> {
+            self.only_agent(self.env().caller())?;
+            self.identities.insert(account, Identity {
+                country,
+                registered_at: self.env().block_timestamp(),
+            });
+            self.env().emit_event(IdentityRegistered { account, country });
+            Ok(())
+        }
+
+        ///Remove the identity of an investor wallet, only by agent.
+        #[ink(message)]
+        pub fn remove_identity(&mut self, account: AccountId) -> Result<()> {
+            self.only_agent(self.env().caller())?;
+            self.identities.take(&account).ok_or(Error::IdentityNotFound)?;
+            self.env().emit_event(IdentityRemoved { account });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn identity(&self, account: AccountId) -> Option<Identity> {
+            self.identities.get(&account).copied()
+        }
+
+        ///Trust issuer for the given claim topics, an empty list removes it, only by owner.
+        #[ink(message)]
+        pub fn set_trusted_issuer(&mut self, issuer: AccountId, topics: Vec<u32>) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            Self::valid_topics(&topics)?;
+            if topics.is_empty() {
+                self.trusted_issuers.take(&issuer);
+            } else {
+                self.trusted_issuers.insert(issuer, topics.clone());
+            }
+            self.env().emit_event(TrustedIssuerUpdated { issuer, topics });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn trusted_topics(&self, issuer: AccountId) -> Vec<u32> {
+            self.trusted_issuers.get(&issuer).cloned().unwrap_or_default()
+        }
+
+        ///Set the claim topics every investor must hold, at most `MAX_TOPICS`, only by owner.
+        #[ink(message)]
+        pub fn set_required_topics(&mut self, topics: Vec<u32>) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            Self::valid_topics(&topics)?;
+            self.required_topics = topics.clone();
+
+            self.env().emit_event(RequiredTopicsUpdated { topics });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn required_topics(&self) -> Vec<u32> {
+            self.required_topics.clone()
+        }
+
+        ///Add a claim to a registered identity, only by an issuer trusted for the topic.
+        #[ink(message)]
+        pub fn add_claim(&mut self, account: AccountId, topic: u32, data: Hash, expires_at: u64) -> Result<()> {
+            let issuer = self.env().caller();
+            if !self.is_trusted_for(issuer, topic) {
+                return Err(Error::NotTrustedIssuer);
+            }
+            if !self.identities.contains_key(&account) {
+                return Err(Error::IdentityNotFound);
+            }
+
+            self.claims.insert((account, topic), Claim {
+                issuer,
+                data,
+                issued_at: self.env().block_timestamp(),
+                expires_at,
+            });
+            self.env().emit_event(ClaimAdded {
+                account,
+                topic,
+                issuer,
+                expires_at,
+            });
+            Ok(())
+        }
+
+        ///Revoke a claim, by its issuer or the owner.
+        #[ink(message)]
+        pub fn revoke_claim(&mut self, account: AccountId, topic: u32) -> Result<()> {
+            let claim = self.claim_of(account, topic).ok_or(Error::ClaimNotFound)?;
+            let caller = self.env().caller();
+            if caller != claim.issuer {
+                self.only_owner(caller)?;
+            }
+            self.claims.take(&(account, topic));
+            self.env().emit_event(ClaimRevoked { account, topic });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn claim_of(&self, account: AccountId, topic: u32) -> Option<Claim> {
+            self.claims.get(&(account, topic)).copied()
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, to: AccountId) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.owner = to;
+            Ok(())
+        }
+
+        fn is_trusted_for(&self, issuer: AccountId, topic: u32) -> bool {
+            self.trusted_issuers
+                .get(&issuer)
+                .map_or(false, |topics| topics.contains(&topic))
+        }
+
+        fn valid_topics(topics: &[u32]) -> Result<()> {
+            if topics.len() > MAX_TOPICS {
+                Err(Error::TooManyTopics)
+            } else {
+                Ok(())
+            }
+        }
+
+        fn only_agent(&self, caller: AccountId) -> Result<()> {
+            if self.is_agent(caller) {
+                Ok(())
+            } else {
+                Err(Error::NotAgent)
+            }
+        }
+
+        fn only_owner(&self, caller: AccountId) -> Result<()> {
+            if *self.owner == caller {
+                Ok(())
+            } else {
+                Err(Error::OnlyOwner)
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        use ink_lang as ink;
+
+        fn set_caller(caller: AccountId) {
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            let mut data =
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4]));
+            data.push_arg(&caller);
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller, callee, 1000000, 0, data,
+            );
+        }
+
+        #[ink::test]
+        fn verification_works() {
+            let mut contract = IdentityRegistry::new();
+            let issuer = AccountId::from([0x2; 32]);
+            let investor = AccountId::from([0x3; 32]);
+            let data = Hash::from([0x7; 32]);
+            assert_eq!(contract.set_trusted_issuer(issuer, vec![KYC]), Ok(()));
+
+            set_caller(issuer);
+            assert_eq!(
+                contract.add_claim(investor, KYC, data, u64::MAX),
+                Err(Error::IdentityNotFound)
+            );
+            assert_eq!(contract.register_identity(investor, 840), Err(Error::NotAgent));
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.register_identity(investor, 840), Ok(()));
+            assert_eq!(contract.investor_country(investor), Some(840));
+            assert!(!contract.is_verified(investor));
+
+            set_caller(issuer);
+            assert_eq!(
+                contract.add_claim(investor, ACCREDITATION, data, u64::MAX),
+                Err(Error::NotTrustedIssuer)
+            );
+            assert_eq!(contract.add_claim(investor, KYC, data, u64::MAX), Ok(()));
+            assert!(contract.is_verified(investor));
+
+            assert_eq!(contract.revoke_claim(investor, KYC), Ok(()));
+            assert!(!contract.is_verified(investor));
+        }
+
+        #[ink::test]
+        fn untrusted_issuer_claims_ignored() {
+            let mut contract = IdentityRegistry::new();
+            let issuer = AccountId::from([0x2; 32]);
+            let investor = AccountId::from([0x3; 32]);
+            assert_eq!(contract.register_identity(investor, 840), Ok(()));
+            assert_eq!(contract.set_trusted_issuer(issuer, vec![KYC]), Ok(()));
+
+            set_caller(issuer);
+            assert_eq!(contract.add_claim(investor, KYC, Hash::from([0x7; 32]), u64::MAX), Ok(()));
+            assert!(contract.is_verified(investor));
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.set_trusted_issuer(issuer, Vec::new()), Ok(()));
+            assert!(!contract.is_verified(investor));
+        }
+
+        #[ink::test]
+        fn topic_lists_are_bounded() {
+            let mut contract = IdentityRegistry::new();
+            let issuer = AccountId::from([0x2; 32]);
+            let too_many: Vec<u32> = (0..MAX_TOPICS as u32 + 1).collect();
+            assert_eq!(
+                contract.set_trusted_issuer(issuer, too_many.clone()),
+                Err(Error::TooManyTopics)
+            );
+            assert_eq!(contract.set_required_topics(too_many.clone()), Err(Error::TooManyTopics));
+            assert_eq!(contract.required_topics(), vec![KYC]);
+
+            let most = too_many[..MAX_TOPICS].to_vec();
+            assert_eq!(contract.set_trusted_issuer(issuer, most.clone()), Ok(()));
+            assert_eq!(contract.set_required_topics(most.clone()), Ok(()));
+            assert_eq!(contract.required_topics(), most);
+        }
+
+        #[ink::test]
+        fn settings_emit_events() {
+            let mut contract = IdentityRegistry::new();
+            let agent = AccountId::from([0x2; 32]);
+            let emitted = ink_env::test::recorded_events().count();
+            assert_eq!(contract.set_agent(agent, true), Ok(()));
+            assert!(contract.is_agent(agent));
+            assert_eq!(contract.set_required_topics(vec![KYC, ACCREDITATION]), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), emitted + 2);
+        }
+    }
+}
@@ -0,0 +1,1176 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+#[ink::contract]
+mod multi_stable_currency {
+
+    use ink_env::call::FromAccountId;
+    use ink_prelude::{string::String, vec::Vec};
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        lazy::Lazy,
+        traits::{PackedLayout, SpreadLayout},
+    };
+    use price_oracle::{PriceFeed, PriceOracle};
+
+    pub type CurrencyId = u32;
+
+    /// Precision of conversion rates: units of the target currency per unit
+    /// of the source currency.
+    const RATE_PRECISION: Balance = 1_000_000_000;
+    /// Length of the outflow buckets, in milliseconds.
+    const HOUR: u64 = 60 * 60 * 1000;
+    const DAY: u64 = 24 * HOUR;
+    /// Hourly buckets in the rolling daily window, daily buckets in the rolling monthly one.
+    const HOURS_PER_DAY: u64 = 24;
+    const DAYS_PER_MONTH: u64 = 30;
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        InsufficientBalance,
+        InsufficientAllowance,
+        OnlyOwner,
+        NotPermission,
+        UnknownCurrency,
+        CurrencyExists,
+        InvalidFeeSchedule,
+        RateNotAvailable,
+        SlippageExceeded,
+        ConversionOverflow,
+        Blacklisted,
+        LimitExceeded,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Currency {
+        pub symbol: String,
+        pub decimals: u8,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct FeeSchedule {
+        /// Escrow fee rate, in basis points.
+        pub bps: u32,
+        /// Lowest escrow fee charged per payment.
+        pub min: Balance,
+        /// Highest escrow fee charged per payment.
+        pub max: Balance,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct LimitTier {
+        /// Largest outflow within any rolling day.
+        pub daily: Balance,
+        /// Largest outflow within any rolling 30 days.
+        pub monthly: Balance,
+    }
+
+    /// Outflow counted against an account's limit, in hourly buckets for the
+    /// rolling day and daily buckets for the rolling 30 days.
+    #[derive(Debug, Default, PartialEq, Eq, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Outflow {
+        /// (hour, value) buckets, oldest first.
+        pub hours: Vec<(u64, Balance)>,
+        /// (day, value) buckets, oldest first.
+        pub days: Vec<(u64, Balance)>,
+    }
+
+    #[ink(storage)]
+    pub struct MultiStableCurrency {
+        ///Owner of Contract.
+        owner: Lazy<AccountId>,
+        /// Registered currencies by id.
+        currencies: StorageHashMap<CurrencyId, Currency>,
+        /// Mapping from symbol to currency id.
+        currency_ids: StorageHashMap<String, CurrencyId>,
+        /// Id of the next registered currency.
+        next_currency_id: Lazy<CurrencyId>,
+        /// Total token supply of each currency.
+        total_supply: StorageHashMap<CurrencyId, Balance>,
+        /// Mapping from currency and owner to number of owned token.
+        balances: StorageHashMap<(CurrencyId, AccountId), Balance>,
+        /// Mapping of the token amount which an account is allowed to withdraw from another account.
+        allowances: StorageHashMap<(CurrencyId, AccountId, AccountId), Balance>,
+        ///ecrow balance
+        escrow_balances: StorageHashMap<(CurrencyId, AccountId, AccountId), Balance>,
+        /// Fee reserved with each escrow, paid out on completion.
+        escrow_fees: StorageHashMap<(CurrencyId, AccountId, AccountId), Balance>,
+        /// Escrow fee schedule of each currency.
+        fee_schedules: StorageHashMap<CurrencyId, FeeSchedule>,
+        /// Account receiving escrow fees and conversion spread of every currency.
+        fee_recipient: Lazy<AccountId>,
+        /// Admin set conversion rates, scaled by `RATE_PRECISION`.
+        rates: StorageHashMap<(CurrencyId, CurrencyId), Balance>,
+        /// Price oracle used for pairs without an admin set rate.
+        price_oracle: Lazy<Option<AccountId>>,
+        /// Spread kept on each conversion, in basis points, minted to the fee recipient.
+        spread_bps: Lazy<u32>,
+        /// Accounts holding the compliance role.
+        compliance_officers: StorageHashMap<AccountId, bool>,
+        /// Sanctioned accounts that can neither send nor receive any currency.
+        blacklist: StorageHashMap<AccountId, bool>,
+        /// Outflow limits by currency and tier. Tier 0 applies to accounts without
+        /// a tier, a tier that is not configured is unlimited. The fee recipient is
+        /// never limited.
+        limit_tiers: StorageHashMap<(CurrencyId, u8), LimitTier>,
+        /// Limit tier assigned to each account.
+        account_tiers: StorageHashMap<AccountId, u8>,
+        /// Outflow of each account per currency within the rolling day and 30 days.
+        outflows: StorageHashMap<(CurrencyId, AccountId), Outflow>,
+    }
+
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        currency: CurrencyId,
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        currency: CurrencyId,
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CurrencyRegistered {
+        #[ink(topic)]
+        currency: CurrencyId,
+        symbol: String,
+        decimals: u8,
+    }
+
+    #[ink(event)]
+    pub struct FeeScheduleUpdated {
+        #[ink(topic)]
+        currency: CurrencyId,
+        bps: u32,
+        min: Balance,
+        max: Balance,
+    }
+
+    #[ink(event)]
+    pub struct RateUpdated {
+        #[ink(topic)]
+        from: CurrencyId,
+        #[ink(topic)]
+        to: CurrencyId,
+        rate: Option<Balance>,
+    }
+
+    #[ink(event)]
+    pub struct Converted {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        from: CurrencyId,
+        #[ink(topic)]
+        to: CurrencyId,
+        amount_in: Balance,
+        amount_out: Balance,
+        rate: Balance,
+        spread_bps: u32,
+    }
+
+    #[ink(event)]
+    pub struct FeeRecipientUpdated {
+        #[ink(topic)]
+        recipient: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct SpreadUpdated {
+        bps: u32,
+    }
+
+    #[ink(event)]
+    pub struct PriceOracleUpdated {
+        oracle: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct ComplianceOfficerUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct BlacklistUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        officer: AccountId,
+        blacklisted: bool,
+    }
+
+    #[ink(event)]
+    pub struct LimitTierUpdated {
+        #[ink(topic)]
+        currency: CurrencyId,
+        #[ink(topic)]
+        tier: u8,
+        daily: Balance,
+        monthly: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AccountTierUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        tier: u8,
+    }
+
+    impl Default for MultiStableCurrency {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl MultiStableCurrency {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            let caller = Self::env().caller();
+            Self {
+                owner: Lazy::new(caller),
+                currencies: StorageHashMap::new(),
+                currency_ids: StorageHashMap::new(),
+                next_currency_id: Lazy::new(0),
+                total_supply: StorageHashMap::new(),
+                balances: StorageHashMap::new(),
+                allowances: StorageHashMap::new(),
+                escrow_balances: StorageHashMap::new(),
+                escrow_fees: StorageHashMap::new(),
+                fee_schedules: StorageHashMap::new(),
+                fee_recipient: Lazy::new(caller),
+                rates: StorageHashMap::new(),
+                price_oracle: Lazy::new(None),
+                spread_bps: Lazy::new(0),
+                compliance_officers: StorageHashMap::new(),
+                blacklist: StorageHashMap::new(),
+                limit_tiers: StorageHashMap::new(),
+                account_tiers: StorageHashMap::new(),
+                outflows: StorageHashMap::new(),
+            }
+        }
+
+        ///Register a new currency, only by owner. Returns the currency id.
+        #[ink(message)]
+        pub fn register_currency(&mut self, symbol: String, decimals: u8) -> Result<CurrencyId> {
+            self.only_owner(self.env().caller())?;
+            if self.currency_ids.contains_key(&symbol) {
+                return Err(Error::CurrencyExists);
+            }
+
+            let currency = *self.next_currency_id;
+            *self.next_currency_id += 1;
+            self.currencies.insert(currency, Currency { symbol: symbol.clone(), decimals });
+            self.currency_ids.insert(symbol.clone(), currency);
+            self.fee_schedules.insert(currency, FeeSchedule { bps: 100, min: 1, max: Balance::MAX });
+
+            self.env().emit_event(CurrencyRegistered {
+                currency,
+                symbol,
+                decimals,
+            });
+            Ok(currency)
+        }
+
+        #[ink(message)]
+        pub fn currency(&self, currency: CurrencyId) -> Option<Currency> {
+            self.currencies.get(&currency).cloned()
+        }
+
+        #[ink(message)]
+        pub fn currency_id(&self, symbol: String) -> Option<CurrencyId> {
+            self.currency_ids.get(&symbol).copied()
+        }
+
+        ///get ids of all registered currency
+        #[ink(message)]
+        pub fn currencies(&self) -> Vec<CurrencyId> {
+            (0..*self.next_currency_id).collect()
+        }
+
+        #[ink(message)]
+        pub fn total_supply(&self, currency: CurrencyId) -> Balance {
+            *self.total_supply.get(&currency).unwrap_or(&0)
+        }
+
+        #[ink(message)]
+        pub fn token_symbol(&self, currency: CurrencyId) -> Option<String> {
+            self.currency(currency).map(|c| c.symbol)
+        }
+
+        #[ink(message)]
+        pub fn balance_of(&self, currency: CurrencyId, owner: AccountId) -> Balance {
+            self.balance_of_or_zero(currency, &owner)
+        }
+
+        #[ink(message)]
+        pub fn approve(&mut self, currency: CurrencyId, spender: AccountId, value: Balance) -> bool {
+            // Record the new allowance.
+            let owner = self.env().caller();
+            if self.known_currency(currency).is_err()
+                || self.is_blacklisted(owner)
+                || self.is_blacklisted(spender)
+            {
+                return false;
+            }
+            self.allowances.insert((currency, owner, spender), value);
+
+            // Notify offchain users of the approval and report success.
+            self.env().emit_event(Approval {
+                currency,
+                owner,
+                spender,
+                value,
+            });
+            true
+        }
+
+        #[ink(message)]
+        pub fn allowance(&self, currency: CurrencyId, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowance_of_or_zero(currency, &owner, &spender)
+        }
+
+        #[ink(message)]
+        pub fn transfer_from(
+            &mut self,
+            currency: CurrencyId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            // Ensure that a sufficient allowance exists.
+            let caller = self.env().caller();
+            self.not_blacklisted(&caller)?;
+            let allowance = self.allowance_of_or_zero(currency, &from, &caller);
+            if allowance < value {
+                return Err(Error::InsufficientAllowance);
+            }
+
+            self.transfer_from_to(currency, from, to, value)?;
+            self.allowances.insert((currency, from, caller), allowance - value);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn transfer(&mut self, currency: CurrencyId, to: AccountId, value: Balance) -> Result<()> {
+            self.transfer_from_to(currency, self.env().caller(), to, value)
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, to: AccountId) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.owner = to;
+            Ok(())
+        }
+
+        ///Increment total supply of currency to owner, only by owner.
+        #[ink(message)]
+        pub fn inc_supply(&mut self, currency: CurrencyId, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.only_owner(caller)?;
+            self.known_currency(currency)?;
+            self.mint_to(currency, caller, value);
+            Ok(())
+        }
+
+        ///Decrement total supply of currency from owner, only by owner.
+        #[ink(message)]
+        pub fn dec_supply(&mut self, currency: CurrencyId, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.only_owner(caller)?;
+            self.burn_from(currency, caller, value)
+        }
+
+        ///Set escrow fee rate of currency in basis point, bounded by min and max fee, only by owner.
+        #[ink(message)]
+        pub fn set_fee_schedule(
+            &mut self,
+            currency: CurrencyId,
+            bps: u32,
+            min: Balance,
+            max: Balance,
+        ) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.known_currency(currency)?;
+            if bps > 10_000 || min > max {
+                return Err(Error::InvalidFeeSchedule);
+            }
+            self.fee_schedules.insert(currency, FeeSchedule { bps, min, max });
+
+            self.env().emit_event(FeeScheduleUpdated {
+                currency,
+                bps,
+                min,
+                max,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn fee_schedule(&self, currency: CurrencyId) -> Option<FeeSchedule> {
+            self.fee_schedules.get(&currency).copied()
+        }
+
+        #[ink(message)]
+        pub fn set_fee_recipient(&mut self, recipient: AccountId) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.fee_recipient = recipient;
+            self.env().emit_event(FeeRecipientUpdated { recipient });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn fee_recipient(&self) -> AccountId {
+            *self.fee_recipient
+        }
+
+        ///Set conversion rate of a pair scaled by 10^9, only by owner.
+        #[ink(message)]
+        pub fn set_rate(&mut self, from: CurrencyId, to: CurrencyId, rate: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.known_currency(from)?;
+            self.known_currency(to)?;
+            self.rates.insert((from, to), rate);
+
+            self.env().emit_event(RateUpdated { from, to, rate: Some(rate) });
+            Ok(())
+        }
+
+        ///Remove admin set rate so the pair falls back to the price oracle, only by owner.
+        #[ink(message)]
+        pub fn remove_rate(&mut self, from: CurrencyId, to: CurrencyId) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.known_currency(from)?;
+            self.known_currency(to)?;
+            self.rates.take(&(from, to));
+
+            self.env().emit_event(RateUpdated { from, to, rate: None });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_price_oracle(&mut self, oracle: Option<AccountId>) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.price_oracle = oracle;
+            self.env().emit_event(PriceOracleUpdated { oracle });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn price_oracle(&self) -> Option<AccountId> {
+            *self.price_oracle
+        }
+
+        #[ink(message)]
+        pub fn set_spread(&mut self, bps: u32) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            if bps > 10_000 {
+                return Err(Error::InvalidFeeSchedule);
+            }
+            *self.spread_bps = bps;
+            self.env().emit_event(SpreadUpdated { bps });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn spread(&self) -> u32 {
+            *self.spread_bps
+        }
+
+        ///get conversion rate of a pair, from admin table first and price oracle otherwise
+        #[ink(message)]
+        pub fn rate(&self, from: CurrencyId, to: CurrencyId) -> Option<Balance> {
+            let oracle = *self.price_oracle;
+            self.rate_with(from, to, |symbol| Self::oracle_price(oracle, symbol))
+        }
+
+        ///Burn `amount` of one currency from the caller and mint the other at the current
+        ///rate less spread, the spread is minted to the fee recipient. Fails when the
+        ///result is below `min_out`.
+        #[ink(message)]
+        pub fn convert(
+            &mut self,
+            from_symbol: String,
+            to_symbol: String,
+            amount: Balance,
+            min_out: Balance,
+        ) -> Result<Balance> {
+            let oracle = *self.price_oracle;
+            self.convert_with(from_symbol, to_symbol, amount, min_out, |symbol| {
+                Self::oracle_price(oracle, symbol)
+            })
+        }
+
+        /// `convert` with the oracle prices read through `price_of`.
+        fn convert_with(
+            &mut self,
+            from_symbol: String,
+            to_symbol: String,
+            amount: Balance,
+            min_out: Balance,
+            price_of: impl Fn(String) -> Option<Balance>,
+        ) -> Result<Balance> {
+            let account = self.env().caller();
+            self.not_blacklisted(&account)?;
+            let from = self.currency_id(from_symbol).ok_or(Error::UnknownCurrency)?;
+            let to = self.currency_id(to_symbol).ok_or(Error::UnknownCurrency)?;
+            let rate = self.rate_with(from, to, price_of).ok_or(Error::RateNotAvailable)?;
+
+            let spread_bps = *self.spread_bps;
+            let gross = amount.checked_mul(rate).ok_or(Error::ConversionOverflow)? / RATE_PRECISION;
+            let spread = gross.checked_mul(spread_bps as Balance).ok_or(Error::ConversionOverflow)? / 10_000;
+            let amount_out = gross - spread;
+            if amount_out < min_out {
+                return Err(Error::SlippageExceeded);
+            }
+
+            self.burn_from(from, account, amount)?;
+            self.mint_to(to, account, amount_out);
+            if spread > 0 {
+                let recipient = *self.fee_recipient;
+                self.mint_to(to, recipient, spread);
+            }
+
+            self.env().emit_event(Converted {
+                account,
+                from,
+                to,
+                amount_in: amount,
+                amount_out,
+                rate,
+                spread_bps,
+            });
+            Ok(amount_out)
+        }
+
+        ///Grant or revoke the compliance role, only by owner.
+        #[ink(message)]
+        pub fn set_compliance_officer(&mut self, account: AccountId, allowed: bool) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.compliance_officers.insert(account, allowed);
+
+            self.env().emit_event(ComplianceOfficerUpdated { account, allowed });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_compliance_officer(&self, account: AccountId) -> bool {
+            *self.compliance_officers.get(&account).unwrap_or(&false)
+        }
+
+        #[ink(message)]
+        pub fn blacklist(&mut self, account: AccountId) -> Result<()> {
+            self.set_blacklisted(account, true)
+        }
+
+        #[ink(message)]
+        pub fn unblacklist(&mut self, account: AccountId) -> Result<()> {
+            self.set_blacklisted(account, false)
+        }
+
+        #[ink(message)]
+        pub fn is_blacklisted(&self, account: AccountId) -> bool {
+            *self.blacklist.get(&account).unwrap_or(&false)
+        }
+
+        ///Set daily and monthly outflow limit of a tier in currency, only by owner.
+        #[ink(message)]
+        pub fn set_limit_tier(
+            &mut self,
+            currency: CurrencyId,
+            tier: u8,
+            daily: Balance,
+            monthly: Balance,
+        ) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.known_currency(currency)?;
+            self.limit_tiers.insert((currency, tier), LimitTier { daily, monthly });
+
+            self.env().emit_event(LimitTierUpdated { currency, tier, daily, monthly });
+            Ok(())
+        }
+
+        ///Remove limit of a tier in currency, making it unlimited, only by owner.
+        #[ink(message)]
+        pub fn remove_limit_tier(&mut self, currency: CurrencyId, tier: u8) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.limit_tiers.take(&(currency, tier));
+
+            self.env().emit_event(LimitTierUpdated {
+                currency,
+                tier,
+                daily: Balance::MAX,
+                monthly: Balance::MAX,
+            });
+            Ok(())
+        }
+
+        ///Assign limit tier to account, by owner or compliance officer.
+        #[ink(message)]
+        pub fn assign_tier(&mut self, account: AccountId, tier: u8) -> Result<()> {
+            let caller = self.env().caller();
+            if caller != *self.owner && !self.is_compliance_officer(caller) {
+                return Err(Error::NotPermission);
+            }
+            self.account_tiers.insert(account, tier);
+
+            self.env().emit_event(AccountTierUpdated { account, tier });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn tier_of(&self, account: AccountId) -> u8 {
+            *self.account_tiers.get(&account).unwrap_or(&0)
+        }
+
+        #[ink(message)]
+        pub fn limit_tier(&self, currency: CurrencyId, tier: u8) -> Option<LimitTier> {
+            self.limit_tiers.get(&(currency, tier)).copied()
+        }
+
+        ///get amount of currency account can still send within the rolling day and 30 days
+        #[ink(message)]
+        pub fn remaining_limit(&self, currency: CurrencyId, account: AccountId) -> (Balance, Balance) {
+            let tier = match self.limit_of(currency, &account) {
+                None => return (Balance::MAX, Balance::MAX),
+                Some(t) => t,
+            };
+            let (daily, monthly) = Self::outflow_totals(&self.current_outflow(currency, &account));
+            (
+                tier.daily.saturating_sub(daily),
+                tier.monthly.saturating_sub(monthly),
+            )
+        }
+
+        #[ink(message)]
+        pub fn create_payment(&mut self, currency: CurrencyId, seller: AccountId, value: Balance) -> Result<()> {
+            let order = self.env().caller();
+            self.not_blacklisted(&order)?;
+            self.not_blacklisted(&seller)?;
+            let fee = self.cal_fee(currency, value)?;
+            let total = value.checked_add(fee).ok_or(Error::InsufficientBalance)?;
+
+            let order_balance = self.balance_of_or_zero(currency, &order);
+            if order_balance < total {
+                return Err(Error::InsufficientBalance);
+            }
+            self.spend_limit(currency, order, total)?;
+            self.balances.insert((currency, order), order_balance - total);
+
+            let escrow_balance = self.escrow_of_or_zero(currency, &order, &seller);
+            self.escrow_balances
+                .insert((currency, order, seller), escrow_balance + total);
+            let escrow_fee = *self.escrow_fees.get(&(currency, order, seller)).unwrap_or(&0);
+            self.escrow_fees.insert((currency, order, seller), escrow_fee + fee);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn complete_payment(&mut self, currency: CurrencyId, from: AccountId, to: AccountId) -> Result<()> {
+            let caller = self.env().caller();
+
+            if caller == from || caller == *self.owner {
+                self.not_blacklisted(&from)?;
+                self.not_blacklisted(&to)?;
+                let esbalance = self.escrow_of_or_zero(currency, &from, &to);
+                let fee = *self.escrow_fees.get(&(currency, from, to)).unwrap_or(&0);
+
+                let tobalance = self.balance_of_or_zero(currency, &to);
+                self.balances.insert((currency, to), esbalance - fee + tobalance);
+
+                let recipient = *self.fee_recipient;
+                let recipient_balance = self.balance_of_or_zero(currency, &recipient);
+                self.balances.insert((currency, recipient), fee + recipient_balance);
+
+                self.escrow_balances.insert((currency, from, to), 0);
+                self.escrow_fees.insert((currency, from, to), 0);
+                Ok(())
+            } else {
+                Err(Error::NotPermission)
+            }
+        }
+
+        #[ink(message)]
+        pub fn refund(&mut self, currency: CurrencyId, from: AccountId, to: AccountId) -> Result<()> {
+            let caller = self.env().caller();
+
+            if caller == to || caller == *self.owner {
+                // Escrow goes back even to a blacklisted buyer, where it stays frozen.
+                self.not_blacklisted(&caller)?;
+                let esbalance = self.escrow_of_or_zero(currency, &from, &to);
+                let balance = self.balance_of_or_zero(currency, &from);
+                self.balances.insert((currency, from), esbalance + balance);
+
+                self.escrow_balances.insert((currency, from, to), 0);
+                self.escrow_fees.insert((currency, from, to), 0);
+                Ok(())
+            } else {
+                Err(Error::NotPermission)
+            }
+        }
+
+        #[ink(message)]
+        pub fn escrow_balance(&self, currency: CurrencyId, from: AccountId, to: AccountId) -> Balance {
+            self.escrow_of_or_zero(currency, &from, &to)
+        }
+
+        fn transfer_from_to(
+            &mut self,
+            currency: CurrencyId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            self.known_currency(currency)?;
+            self.not_blacklisted(&from)?;
+            self.not_blacklisted(&to)?;
+            let from_balance = self.balance_of_or_zero(currency, &from);
+            if from_balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            self.spend_limit(currency, from, value)?;
+
+            // Update the sender's balance.
+            self.balances.insert((currency, from), from_balance - value);
+
+            // Update the receiver's balance.
+            let to_balance = self.balance_of_or_zero(currency, &to);
+            self.balances.insert((currency, to), to_balance + value);
+
+            self.env().emit_event(Transfer {
+                currency,
+                from: Some(from),
+                to: Some(to),
+                value,
+            });
+            Ok(())
+        }
+
+        fn set_blacklisted(&mut self, account: AccountId, blacklisted: bool) -> Result<()> {
+            let officer = self.env().caller();
+            if !self.is_compliance_officer(officer) {
+                return Err(Error::NotPermission);
+            }
+            self.blacklist.insert(account, blacklisted);
+
+            self.env().emit_event(BlacklistUpdated {
+                account,
+                officer,
+                blacklisted,
+            });
+            Ok(())
+        }
+
+        fn not_blacklisted(&self, account: &AccountId) -> Result<()> {
+            if self.is_blacklisted(*account) {
+                Err(Error::Blacklisted)
+            } else {
+                Ok(())
+            }
+        }
+
+        fn spend_limit(&mut self, currency: CurrencyId, account: AccountId, value: Balance) -> Result<()> {
+            if self.limit_of(currency, &account).is_none() {
+                return Ok(());
+            }
+            let (daily, monthly) = self.remaining_limit(currency, account);
+            if value > daily || value > monthly {
+                return Err(Error::LimitExceeded);
+            }
+
+            let now = self.env().block_timestamp();
+            let mut outflow = self.current_outflow(currency, &account);
+            Self::add_to_bucket(&mut outflow.hours, now / HOUR, value);
+            Self::add_to_bucket(&mut outflow.days, now / DAY, value);
+            self.outflows.insert((currency, account), outflow);
+            Ok(())
+        }
+
+        fn limit_of(&self, currency: CurrencyId, account: &AccountId) -> Option<LimitTier> {
+            if *account == *self.fee_recipient {
+                return None;
+            }
+            self.limit_tier(currency, self.tier_of(*account))
+        }
+
+        /// Outflow of account in currency, without the buckets that left the rolling windows.
+        fn current_outflow(&self, currency: CurrencyId, account: &AccountId) -> Outflow {
+            let now = self.env().block_timestamp();
+            let (hour, day) = (now / HOUR, now / DAY);
+            let mut outflow = self.outflows.get(&(currency, *account)).cloned().unwrap_or_default();
+            outflow.hours.retain(|(h, _)| hour.saturating_sub(*h) < HOURS_PER_DAY);
+            outflow.days.retain(|(d, _)| day.saturating_sub(*d) < DAYS_PER_MONTH);
+            outflow
+        }
+
+        fn add_to_bucket(buckets: &mut Vec<(u64, Balance)>, key: u64, value: Balance) {
+            match buckets.last_mut() {
+                Some((last, spent)) if *last == key => *spent = spent.saturating_add(value),
+                _ => buckets.push((key, value)),
+            }
+        }
+
+        /// Outflow within the rolling day and the rolling 30 days.
+        fn outflow_totals(outflow: &Outflow) -> (Balance, Balance) {
+            let sum = |buckets: &Vec<(u64, Balance)>| {
+                buckets.iter().fold(0, |total: Balance, (_, spent)| total.saturating_add(*spent))
+            };
+            (sum(&outflow.hours), sum(&outflow.days))
+        }
+
+        /// Admin set rate of the pair, or the rate between the prices `price_of` reports.
+        fn rate_with(
+            &self,
+            from: CurrencyId,
+            to: CurrencyId,
+            price_of: impl Fn(String) -> Option<Balance>,
+        ) -> Option<Balance> {
+            if let Some(rate) = self.rates.get(&(from, to)) {
+                return Some(*rate);
+            }
+
+            let from_currency = self.currency(from)?;
+            let to_currency = self.currency(to)?;
+            let from_price = price_of(from_currency.symbol)?;
+            let to_price = price_of(to_currency.symbol)?;
+            Self::oracle_rate(from_price, from_currency.decimals, to_price, to_currency.decimals)
+        }
+
+        fn oracle_price(oracle: Option<AccountId>, symbol: String) -> Option<Balance> {
+            let oracle: PriceOracle = FromAccountId::from_account_id(oracle?);
+            oracle.latest_price(symbol)
+        }
+
+        /// Rate between two oracle prices quoted per whole token, scaled to the
+        /// currencies' smallest units.
+        fn oracle_rate(from_price: Balance, from_decimals: u8, to_price: Balance, to_decimals: u8) -> Option<Balance> {
+            let from_unit = (10 as Balance).checked_pow(from_decimals as u32)?;
+            let to_unit = (10 as Balance).checked_pow(to_decimals as u32)?;
+            from_price
+                .checked_mul(to_unit)?
+                .checked_mul(RATE_PRECISION)?
+                .checked_div(to_price.checked_mul(from_unit)?)
+        }
+
+        fn mint_to(&mut self, currency: CurrencyId, to: AccountId, value: Balance) {
+            let supply = self.total_supply(currency);
+            self.total_supply.insert(currency, supply + value);
+            let balance = self.balance_of_or_zero(currency, &to);
+            self.balances.insert((currency, to), balance + value);
+
+            self.env().emit_event(Transfer {
+                currency,
+                from: None,
+                to: Some(to),
+                value,
+            });
+        }
+
+        fn burn_from(&mut self, currency: CurrencyId, from: AccountId, value: Balance) -> Result<()> {
+            let balance = self.balance_of_or_zero(currency, &from);
+            if balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            let supply = self.total_supply(currency);
+            self.total_supply.insert(currency, supply - value);
+            self.balances.insert((currency, from), balance - value);
+
+            self.env().emit_event(Transfer {
+                currency,
+                from: Some(from),
+                to: None,
+                value,
+            });
+            Ok(())
+        }
+
+        fn cal_fee(&self, currency: CurrencyId, value: Balance) -> Result<Balance> {
+            let schedule = self.fee_schedule(currency).ok_or(Error::UnknownCurrency)?;
+            let fee = value.saturating_mul(schedule.bps as Balance) / 10_000;
+            Ok(core::cmp::min(core::cmp::max(fee, schedule.min), schedule.max))
+        }
+
+        fn known_currency(&self, currency: CurrencyId) -> Result<()> {
+            if self.currencies.contains_key(&currency) {
+                Ok(())
+            } else {
+                Err(Error::UnknownCurrency)
+            }
+        }
+
+        fn escrow_of_or_zero(&self, currency: CurrencyId, order: &AccountId, seller: &AccountId) -> Balance {
+            *self.escrow_balances.get(&(currency, *order, *seller)).unwrap_or(&0)
+        }
+
+        fn only_owner(&self, caller: AccountId) -> Result<()> {
+            if *self.owner == caller {
+                Ok(())
+            } else {
+                return Err(Error::OnlyOwner);
+            }
+        }
+
+        fn balance_of_or_zero(&self, currency: CurrencyId, owner: &AccountId) -> Balance {
+            *self.balances.get(&(currency, *owner)).unwrap_or(&0)
+        }
+
+        fn allowance_of_or_zero(&self, currency: CurrencyId, owner: &AccountId, spender: &AccountId) -> Balance {
+            *self.allowances.get(&(currency, *owner, *spender)).unwrap_or(&0)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        use ink_lang as ink;
+
+        fn setup() -> (MultiStableCurrency, CurrencyId, CurrencyId) {
+            let mut contract = MultiStableCurrency::new();
+            let usd = contract.register_currency("USD".to_string(), 2).unwrap();
+            let khr = contract.register_currency("KHR".to_string(), 0).unwrap();
+            (contract, usd, khr)
+        }
+
+        #[ink::test]
+        fn register_currency_works() {
+            let (mut contract, usd, khr) = setup();
+            assert_eq!(contract.currencies(), vec![usd, khr]);
+            assert_eq!(contract.currency_id("KHR".to_string()), Some(khr));
+            assert_eq!(
+                contract.currency(usd),
+                Some(Currency { symbol: "USD".to_string(), decimals: 2 })
+            );
+            assert_eq!(
+                contract.register_currency("USD".to_string(), 2),
+                Err(Error::CurrencyExists)
+            );
+        }
+
+        #[ink::test]
+        fn supply_is_per_currency() {
+            let (mut contract, usd, khr) = setup();
+            let owner = AccountId::from([0x1; 32]);
+            assert_eq!(contract.inc_supply(usd, 100), Ok(()));
+            assert_eq!(contract.inc_supply(khr, 400_000), Ok(()));
+            assert_eq!(contract.inc_supply(7, 1), Err(Error::UnknownCurrency));
+            assert_eq!(contract.dec_supply(usd, 10), Ok(()));
+            assert_eq!(contract.total_supply(usd), 90);
+            assert_eq!(contract.total_supply(khr), 400_000);
+            assert_eq!(contract.balance_of(usd, owner), 90);
+            assert_eq!(contract.dec_supply(usd, 91), Err(Error::InsufficientBalance));
+        }
+
+        #[ink::test]
+        fn transfer_works() {
+            let (mut contract, usd, khr) = setup();
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.inc_supply(usd, 100), Ok(()));
+            assert_eq!(contract.transfer(usd, bob, 10), Ok(()));
+            assert_eq!(contract.balance_of(usd, bob), 10);
+            assert_eq!(contract.transfer(khr, bob, 10), Err(Error::InsufficientBalance));
+
+            let alice = AccountId::from([0x1; 32]);
+            assert!(!contract.approve(7, alice, 20));
+            assert!(contract.approve(usd, alice, 20));
+            assert_eq!(contract.allowance(usd, alice, alice), 20);
+            assert_eq!(contract.allowance(khr, alice, alice), 0);
+            assert_eq!(contract.transfer_from(usd, alice, bob, 20), Ok(()));
+            assert_eq!(contract.balance_of(usd, bob), 30);
+        }
+
+        #[ink::test]
+        fn convert_works() {
+            let (mut contract, usd, khr) = setup();
+            let owner = AccountId::from([0x1; 32]);
+            let treasury = AccountId::from([0x9; 32]);
+            assert_eq!(contract.set_fee_recipient(treasury), Ok(()));
+            assert_eq!(contract.inc_supply(usd, 1_000), Ok(()));
+            assert_eq!(
+                contract.convert("USD".to_string(), "KHR".to_string(), 100, 0),
+                Err(Error::RateNotAvailable)
+            );
+
+            // 1 USD cent is 41 KHR.
+            assert_eq!(contract.set_rate(usd, khr, 41 * RATE_PRECISION), Ok(()));
+            assert_eq!(contract.set_spread(100), Ok(()));
+            assert_eq!(
+                contract.convert("USD".to_string(), "KHR".to_string(), 100, 4060),
+                Err(Error::SlippageExceeded)
+            );
+            assert_eq!(
+                contract.convert("USD".to_string(), "KHR".to_string(), 100, 4059),
+                Ok(4059)
+            );
+            assert_eq!(contract.balance_of(usd, owner), 900);
+            assert_eq!(contract.balance_of(khr, owner), 4059);
+            assert_eq!(contract.balance_of(khr, treasury), 41);
+            assert_eq!(contract.total_supply(usd), 900);
+            assert_eq!(contract.total_supply(khr), 4100);
+            assert_eq!(
+                contract.convert("USD".to_string(), "EUR".to_string(), 100, 0),
+                Err(Error::UnknownCurrency)
+            );
+            assert_eq!(
+                contract.convert("USD".to_string(), "KHR".to_string(), Balance::MAX, 0),
+                Err(Error::ConversionOverflow)
+            );
+        }
+
+        #[ink::test]
+        fn oracle_rate_works() {
+            let (contract, usd, khr) = setup();
+            assert_eq!(contract.rate(usd, khr), None);
+
+            // 1 USD is 4100 KHR, so 1 USD cent is 41 KHR.
+            let usd_price = 1_000_000;
+            let khr_price = 1_000_000 / 4100;
+            assert_eq!(
+                MultiStableCurrency::oracle_rate(4100 * khr_price, 2, khr_price, 0),
+                Some(41 * RATE_PRECISION)
+            );
+            assert_eq!(
+                MultiStableCurrency::oracle_rate(khr_price, 0, usd_price, 2),
+                Some(khr_price * 100 * RATE_PRECISION / usd_price)
+            );
+            assert_eq!(MultiStableCurrency::oracle_rate(usd_price, 2, 0, 0), None);
+            assert_eq!(MultiStableCurrency::oracle_rate(Balance::MAX, 0, 1, 0), None);
+        }
+
+        #[ink::test]
+        fn convert_with_oracle_works() {
+            let (mut contract, usd, khr) = setup();
+            let owner = AccountId::from([0x1; 32]);
+            assert_eq!(contract.inc_supply(usd, 1_000), Ok(()));
+            assert_eq!(contract.set_spread(100), Ok(()));
+
+            // Oracle quotes 1 USD at 4100 KHR.
+            let prices = |symbol: String| match symbol.as_str() {
+                "USD" => Some(4_100_000),
+                "KHR" => Some(1_000),
+                _ => None,
+            };
+            assert_eq!(
+                contract.convert_with("USD".to_string(), "KHR".to_string(), 100, 0, prices),
+                Ok(4059)
+            );
+            assert_eq!(contract.balance_of(khr, owner), 4059);
+            assert_eq!(
+                contract.convert_with("USD".to_string(), "KHR".to_string(), 100, 0, |_| None),
+                Err(Error::RateNotAvailable)
+            );
+
+            // An admin set rate takes precedence over the oracle.
+            assert_eq!(contract.set_rate(usd, khr, 40 * RATE_PRECISION), Ok(()));
+            assert_eq!(
+                contract.convert_with("USD".to_string(), "KHR".to_string(), 100, 0, prices),
+                Ok(3960)
+            );
+        }
+
+        #[ink::test]
+        fn settings_emit_events() {
+            let mut contract = MultiStableCurrency::new();
+            assert_eq!(contract.set_fee_recipient(AccountId::from([0x9; 32])), Ok(()));
+            assert_eq!(contract.set_spread(50), Ok(()));
+            assert_eq!(contract.set_price_oracle(None), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), 3);
+        }
+
+        #[ink::test]
+        fn remove_rate_checks_currency() {
+            let (mut contract, usd, khr) = setup();
+            assert_eq!(contract.remove_rate(usd, 7), Err(Error::UnknownCurrency));
+            assert_eq!(contract.remove_rate(usd, khr), Ok(()));
+        }
+
+        #[ink::test]
+        fn blacklist_works() {
+            let (mut contract, usd, _) = setup();
+            let owner = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.inc_supply(usd, 100), Ok(()));
+            assert_eq!(contract.blacklist(bob), Err(Error::NotPermission));
+            assert_eq!(contract.set_compliance_officer(owner, true), Ok(()));
+            assert_eq!(contract.blacklist(bob), Ok(()));
+
+            assert_eq!(contract.transfer(usd, bob, 10), Err(Error::Blacklisted));
+            assert_eq!(contract.create_payment(usd, bob, 10), Err(Error::Blacklisted));
+            assert!(!contract.approve(usd, bob, 10));
+            assert_eq!(contract.unblacklist(bob), Ok(()));
+            assert_eq!(contract.transfer(usd, bob, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn limit_tier_works() {
+            let (mut contract, usd, khr) = setup();
+            let owner = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_fee_recipient(AccountId::from([0x9; 32])), Ok(()));
+            assert_eq!(contract.inc_supply(usd, 1000), Ok(()));
+            assert_eq!(contract.inc_supply(khr, 1000), Ok(()));
+            assert_eq!(contract.set_limit_tier(usd, 0, 100, 300), Ok(()));
+            assert_eq!(contract.remaining_limit(usd, owner), (100, 300));
+            assert_eq!(contract.remaining_limit(khr, owner), (Balance::MAX, Balance::MAX));
+
+            assert_eq!(contract.transfer(usd, bob, 60), Ok(()));
+            assert_eq!(contract.create_payment(usd, bob, 30), Ok(()));
+            assert_eq!(contract.remaining_limit(usd, owner), (9, 209));
+            assert_eq!(contract.transfer(usd, bob, 10), Err(Error::LimitExceeded));
+            assert_eq!(contract.transfer(khr, bob, 500), Ok(()));
+
+            assert_eq!(contract.assign_tier(owner, 1), Ok(()));
+            assert_eq!(contract.transfer(usd, bob, 100), Ok(()));
+        }
+
+        #[ink::test]
+        fn escrow_works() {
+            let (mut contract, usd, _) = setup();
+            let buyer = AccountId::from([0x1; 32]);
+            let seller = AccountId::from([0x0; 32]);
+            assert_eq!(contract.inc_supply(usd, 100), Ok(()));
+            assert_eq!(contract.create_payment(usd, seller, 30), Ok(()));
+            assert_eq!(contract.escrow_balance(usd, buyer, seller), 31);
+            assert_eq!(contract.complete_payment(usd, buyer, seller), Ok(()));
+            assert_eq!(contract.balance_of(usd, seller), 30);
+            assert_eq!(contract.balance_of(usd, buyer), 70);
+
+            assert_eq!(contract.create_payment(usd, seller, 30), Ok(()));
+            assert_eq!(contract.refund(usd, buyer, seller), Ok(()));
+            assert_eq!(contract.balance_of(usd, buyer), 70);
+        }
+    }
+}
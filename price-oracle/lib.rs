@@ -0,0 +1,453 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+pub use self::price_oracle::{PriceFeed, PriceOracle};
+
+#[ink::contract]
+mod price_oracle {
+
+    use ink_prelude::{string::String, vec::Vec};
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        lazy::Lazy,
+        traits::{PackedLayout, SpreadLayout},
+    };
+
+    /// Largest number of whitelisted reporters, bounds the aggregation loop.
+    const MAX_REPORTERS: usize = 32;
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        OnlyOwner,
+        NotReporter,
+        InvalidPrice,
+        StalePrice,
+        OutOfBand,
+        TooManyReporters,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Report {
+        pub price: Balance,
+        pub timestamp: u64,
+    }
+
+    /// Read access to aggregated prices for other contracts.
+    #[ink::trait_definition]
+    pub trait PriceFeed {
+        /// Median of the fresh reports for `symbol`, or `None` when fewer than
+        /// `min_reporters` reporters have a fresh report.
+        #[ink(message)]
+        fn latest_price(&self, symbol: String) -> Option<Balance>;
+    }
+
+    #[ink(storage)]
+    pub struct PriceOracle {
+        ///Owner of Contract.
+        owner: Lazy<AccountId>,
+        /// Accounts allowed to submit prices.
+        reporters: StorageHashMap<AccountId, bool>,
+        /// Whitelisted accounts, used to collect reports.
+        reporter_list: Vec<AccountId>,
+        /// Latest report of each reporter per symbol.
+        reports: StorageHashMap<(String, AccountId), Report>,
+        /// How long a report counts toward the median, in milliseconds.
+        max_age: Lazy<u64>,
+        /// Largest distance from the other reporters' median a submission may have, in basis points.
+        max_deviation: Lazy<u32>,
+        /// Fresh reports needed before a price is published.
+        min_reporters: Lazy<u32>,
+    }
+
+    #[ink(event)]
+    pub struct PriceSubmitted {
+        #[ink(topic)]
+        symbol: String,
+        #[ink(topic)]
+        reporter: AccountId,
+        price: Balance,
+        timestamp: u64,
+    }
+
+    #[ink(event)]
+    pub struct ReporterUpdated {
+        #[ink(topic)]
+        reporter: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct MaxAgeUpdated {
+        max_age: u64,
+    }
+
+    #[ink(event)]
+    pub struct MaxDeviationUpdated {
+        max_deviation: u32,
+    }
+
+    #[ink(event)]
+    pub struct MinReportersUpdated {
+        min_reporters: u32,
+    }
+
+    #[ink(event)]
+    pub struct TransferOwnerShip {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+    }
+
+    impl PriceFeed for PriceOracle {
+        #[ink(message)]
+        fn latest_price(&self, symbol: String) -> Option<Balance> {
+            let prices = self.fresh_prices(&symbol, None);
+            if prices.len() < *self.min_reporters as usize {
+                return None;
+            }
+            Self::median(prices)
+        }
+    }
+
+    impl PriceOracle {
+        /// max_age: how long a report stays fresh (millisecond)
+        /// max_deviation: accepted distance from the other reporters' median (basis point), 0 to disable
+        /// min_reporters: fresh reports needed to publish a price
+        #[ink(constructor)]
+        pub fn new(max_age: u64, max_deviation: u32, min_reporters: u32) -> Self {
+            let caller = Self::env().caller();
+            Self {
+                owner: Lazy::new(caller),
+                reporters: StorageHashMap::new(),
+                reporter_list: Vec::new(),
+                reports: StorageHashMap::new(),
+                max_age: Lazy::new(max_age),
+                max_deviation: Lazy::new(max_deviation),
+                min_reporters: Lazy::new(min_reporters),
+            }
+        }
+
+        #[ink(message)]
+        pub fn add_reporter(&mut self, reporter: AccountId) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            if !self.reporter_list.contains(&reporter) {
+                if self.reporter_list.len() >= MAX_REPORTERS {
+                    return Err(Error::TooManyReporters);
+                }
+                self.reporter_list.push(reporter);
+            }
+            self.reporters.insert(reporter, true);
+            self.env().emit_event(ReporterUpdated { reporter, allowed: true });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_reporter(&mut self, reporter: AccountId) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.reporters.take(&reporter);
+            self.reporter_list.retain(|account| *account != reporter);
+            self.env().emit_event(ReporterUpdated { reporter, allowed: false });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_reporter(&self, account: AccountId) -> bool {
+            *self.reporters.get(&account).unwrap_or(&false)
+        }
+
+        ///Submit a price observed at `timestamp`. Stale, future-dated and
+        ///out-of-band submissions are rejected. The band is measured against the
+        ///fresh reports of the other reporters, so it does not apply while they
+        ///are all stale.
+        #[ink(message)]
+        pub fn submit_price(&mut self, symbol: String, price: Balance, timestamp: u64) -> Result<()> {
+            let reporter = self.env().caller();
+            if !self.is_reporter(reporter) {
+                return Err(Error::NotReporter);
+            }
+            if price == 0 {
+                return Err(Error::InvalidPrice);
+            }
+
+            let now = self.env().block_timestamp();
+            if timestamp > now || now - timestamp > *self.max_age {
+                return Err(Error::StalePrice);
+            }
+
+            if let Some(median) = Self::median(self.fresh_prices(&symbol, Some(reporter))) {
+                let diff = if price > median { price - median } else { median - price };
+                if *self.max_deviation > 0
+                    && diff.saturating_mul(10_000) > median.saturating_mul(*self.max_deviation as Balance)
+                {
+                    return Err(Error::OutOfBand);
+                }
+            }
+
+            self.reports.insert((symbol.clone(), reporter), Report { price, timestamp });
+            self.env().emit_event(PriceSubmitted {
+                symbol,
+                reporter,
+                price,
+                timestamp,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn report_of(&self, symbol: String, reporter: AccountId) -> Option<Report> {
+            self.reports.get(&(symbol, reporter)).copied()
+        }
+
+        #[ink(message)]
+        pub fn max_age(&self) -> u64 {
+            *self.max_age
+        }
+
+        #[ink(message)]
+        pub fn max_deviation(&self) -> u32 {
+            *self.max_deviation
+        }
+
+        #[ink(message)]
+        pub fn set_max_age(&mut self, max_age: u64) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.max_age = max_age;
+            self.env().emit_event(MaxAgeUpdated { max_age });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_max_deviation(&mut self, max_deviation: u32) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.max_deviation = max_deviation;
+            self.env().emit_event(MaxDeviationUpdated { max_deviation });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn min_reporters(&self) -> u32 {
+            *self.min_reporters
+        }
+
+        #[ink(message)]
+        pub fn set_min_reporters(&mut self, min_reporters: u32) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.min_reporters = min_reporters;
+            self.env().emit_event(MinReportersUpdated { min_reporters });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, to: AccountId) -> Result<()> {
+            let from = self.env().caller();
+            self.only_owner(from)?;
+            *self.owner = to;
+            self.env().emit_event(TransferOwnerShip { from, to });
+            Ok(())
+        }
+
+        /// Prices of the fresh reports for `symbol`, leaving out `exclude`.
+        fn fresh_prices(&self, symbol: &str, exclude: Option<AccountId>) -> Vec<Balance> {
+            let now = self.env().block_timestamp();
+            let symbol = String::from(symbol);
+            self.reporter_list
+                .iter()
+                .filter(|reporter| Some(**reporter) != exclude)
+                .filter_map(|reporter| self.reports.get(&(symbol.clone(), *reporter)))
+                .filter(|report| now - report.timestamp <= *self.max_age)
+                .map(|report| report.price)
+                .collect()
+        }
+
+        fn median(mut prices: Vec<Balance>) -> Option<Balance> {
+            if prices.is_empty() {
+                return None;
+            }
+
+            prices.sort_unstable();
+            let mid = prices.len() / 2;
+            if prices.len() % 2 == 0 {
+                Some(prices[mid - 1] + (prices[mid] - prices[mid - 1]) / 2)
+            } else {
+                Some(prices[mid])
+            }
+        }
+
+        fn only_owner(&self, caller: AccountId) -> Result<()> {
+            if *self.owner == caller {
+                Ok(())
+            } else {
+                return Err(Error::OnlyOwner);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        use ink_lang as ink;
+
+        fn set_caller(caller: AccountId) {
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            let mut data =
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4]));
+            data.push_arg(&caller);
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller, callee, 1000000, 0, data,
+            );
+        }
+
+        fn now() -> u64 {
+            ink_env::block_timestamp::<ink_env::DefaultEnvironment>().unwrap_or(0)
+        }
+
+        fn advance_millis(millis: u64) {
+            let until = now() + millis;
+            while now() < until {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>().unwrap();
+            }
+        }
+
+        #[ink::test]
+        fn submit_price_works() {
+            let mut contract = PriceOracle::new(60_000, 0, 1);
+            let reporter = AccountId::from([0x2; 32]);
+            assert_eq!(
+                contract.submit_price("USD".to_string(), 100, now()),
+                Err(Error::NotReporter)
+            );
+            assert_eq!(contract.add_reporter(reporter), Ok(()));
+
+            set_caller(reporter);
+            assert_eq!(
+                contract.submit_price("USD".to_string(), 0, now()),
+                Err(Error::InvalidPrice)
+            );
+            assert_eq!(
+                contract.submit_price("USD".to_string(), 100, now() + 1),
+                Err(Error::StalePrice)
+            );
+            assert_eq!(contract.submit_price("USD".to_string(), 100, now()), Ok(()));
+            assert_eq!(contract.latest_price("USD".to_string()), Some(100));
+            assert_eq!(contract.latest_price("KHR".to_string()), None);
+        }
+
+        #[ink::test]
+        fn median_works() {
+            let mut contract = PriceOracle::new(60_000, 0, 1);
+            let reporters = [
+                AccountId::from([0x2; 32]),
+                AccountId::from([0x3; 32]),
+                AccountId::from([0x4; 32]),
+            ];
+            for reporter in reporters.iter() {
+                assert_eq!(contract.add_reporter(*reporter), Ok(()));
+            }
+
+            set_caller(reporters[0]);
+            assert_eq!(contract.submit_price("USD".to_string(), 100, now()), Ok(()));
+            set_caller(reporters[1]);
+            assert_eq!(contract.submit_price("USD".to_string(), 120, now()), Ok(()));
+            assert_eq!(contract.latest_price("USD".to_string()), Some(110));
+            set_caller(reporters[2]);
+            assert_eq!(contract.submit_price("USD".to_string(), 500, now()), Ok(()));
+            assert_eq!(contract.latest_price("USD".to_string()), Some(120));
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.remove_reporter(reporters[2]), Ok(()));
+            assert_eq!(contract.latest_price("USD".to_string()), Some(110));
+        }
+
+        #[ink::test]
+        fn out_of_band_rejected() {
+            let mut contract = PriceOracle::new(60_000, 500, 1);
+            let reporter_a = AccountId::from([0x2; 32]);
+            let reporter_b = AccountId::from([0x3; 32]);
+            assert_eq!(contract.add_reporter(reporter_a), Ok(()));
+            assert_eq!(contract.add_reporter(reporter_b), Ok(()));
+
+            set_caller(reporter_a);
+            assert_eq!(contract.submit_price("USD".to_string(), 1000, now()), Ok(()));
+            set_caller(reporter_b);
+            assert_eq!(
+                contract.submit_price("USD".to_string(), 1051, now()),
+                Err(Error::OutOfBand)
+            );
+            assert_eq!(contract.submit_price("USD".to_string(), 1050, now()), Ok(()));
+        }
+
+        #[ink::test]
+        fn quorum_works() {
+            let mut contract = PriceOracle::new(60_000, 0, 2);
+            let reporter_a = AccountId::from([0x2; 32]);
+            let reporter_b = AccountId::from([0x3; 32]);
+            assert_eq!(contract.add_reporter(reporter_a), Ok(()));
+            assert_eq!(contract.add_reporter(reporter_b), Ok(()));
+
+            set_caller(reporter_a);
+            assert_eq!(contract.submit_price("USD".to_string(), 100, now()), Ok(()));
+            assert_eq!(contract.latest_price("USD".to_string()), None);
+            set_caller(reporter_b);
+            assert_eq!(contract.submit_price("USD".to_string(), 120, now()), Ok(()));
+            assert_eq!(contract.latest_price("USD".to_string()), Some(110));
+        }
+
+        #[ink::test]
+        fn band_ignores_own_and_stale_reports() {
+            let mut contract = PriceOracle::new(50, 500, 1);
+            let reporter_a = AccountId::from([0x2; 32]);
+            let reporter_b = AccountId::from([0x3; 32]);
+            assert_eq!(contract.add_reporter(reporter_a), Ok(()));
+            assert_eq!(contract.add_reporter(reporter_b), Ok(()));
+
+            set_caller(reporter_a);
+            assert_eq!(contract.submit_price("USD".to_string(), 1000, now()), Ok(()));
+            // A lone reporter is not held to its own previous report.
+            assert_eq!(contract.submit_price("USD".to_string(), 2000, now()), Ok(()));
+            set_caller(reporter_b);
+            assert_eq!(
+                contract.submit_price("USD".to_string(), 1000, now()),
+                Err(Error::OutOfBand)
+            );
+
+            advance_millis(51);
+            assert_eq!(contract.submit_price("USD".to_string(), 1000, now()), Ok(()));
+            assert_eq!(contract.latest_price("USD".to_string()), Some(1000));
+        }
+
+        #[ink::test]
+        fn reporter_list_is_bounded() {
+            let mut contract = PriceOracle::new(60_000, 0, 1);
+            for i in 0..MAX_REPORTERS {
+                assert_eq!(contract.add_reporter(AccountId::from([i as u8 + 2; 32])), Ok(()));
+            }
+            let extra = AccountId::from([0xff; 32]);
+            assert_eq!(contract.add_reporter(extra), Err(Error::TooManyReporters));
+            assert_eq!(contract.remove_reporter(AccountId::from([0x2; 32])), Ok(()));
+            assert!(!contract.is_reporter(AccountId::from([0x2; 32])));
+            assert_eq!(contract.add_reporter(extra), Ok(()));
+        }
+
+        #[ink::test]
+        fn settings_emit_events() {
+            let mut contract = PriceOracle::new(60_000, 0, 1);
+            assert_eq!(contract.set_max_age(1000), Ok(()));
+            assert_eq!(contract.set_max_deviation(100), Ok(()));
+            assert_eq!(contract.set_min_reporters(3), Ok(()));
+            assert_eq!(contract.transfer_ownership(AccountId::from([0x2; 32])), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), 4);
+            assert_eq!(contract.set_max_age(1), Err(Error::OnlyOwner));
+        }
+    }
+}
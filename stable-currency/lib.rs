@@ -5,8 +5,32 @@ use ink_lang as ink;
 #[ink::contract]
 mod stable_currency {
 
-    use ink_storage::{collections::HashMap as StorageHashMap, lazy::Lazy};
-    use ink_prelude::string::String;
+    use document::Document;
+    use ink_env::call::FromAccountId;
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        lazy::Lazy,
+        traits::{PackedLayout, SpreadLayout},
+    };
+    use ink_prelude::{string::String, vec::Vec};
+    use price_oracle::{PriceFeed, PriceOracle};
+
+    /// Precision of `collateral_price`: stable units per one unit of native SEL.
+    const PRICE_PRECISION: Balance = 1_000_000_000;
+    /// Symbol the price oracle reports native SEL under.
+    const COLLATERAL_SYMBOL: &str = "SEL";
+    /// Length of the outflow buckets, in milliseconds.
+    const HOUR: u64 = 60 * 60 * 1000;
+    const DAY: u64 = 24 * HOUR;
+    /// Hourly buckets in the rolling daily window, daily buckets in the rolling monthly one.
+    const HOURS_PER_DAY: u64 = 24;
+    const DAYS_PER_MONTH: u64 = 30;
+    /// Precision of per-second interest rates and the savings share index.
+    const INTEREST_PRECISION: Balance = 1_000_000_000_000;
+    /// Longest payment reference accepted, in bytes.
+    const MAX_REFERENCE_LEN: usize = 64;
+    /// Most redemption ids scanned by one `redemptions_by_status` call.
+    const MAX_PAGE_SIZE: u64 = 100;
 
     #[derive(Debug, PartialEq, Eq, scale::Encode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -14,11 +38,170 @@ mod stable_currency {
         InsufficientBalance,
         InsufficientAllowance,
         OnlyOwner,
-        NotPermission
+        NotPermission,
+        MintAllowanceExceeded,
+        PriceNotSet,
+        Undercollateralized,
+        VaultNotLiquidatable,
+        TransferFailed,
+        InvalidFeeSchedule,
+        Blacklisted,
+        InvalidAttestation,
+        ExceedsReserve,
+        RedemptionNotFound,
+        RedemptionNotPending,
+        LimitExceeded,
+        HoldNotFound,
+        HoldExpired,
+        CaptureExceedsHold,
+        PendingTransferNotFound,
+        SubscriptionNotFound,
+        InvalidPeriod,
+        PaymentNotDue,
+        ReferenceTooLong,
+        CollectionPending,
+        InvalidAmount,
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
 
+    #[derive(Debug, Default, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Vault {
+        /// Native SEL locked in the vault.
+        pub collateral: Balance,
+        /// Stable currency minted against the collateral.
+        pub debt: Balance,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct ReserveAttestation {
+        pub auditor: AccountId,
+        /// Fiat reserve held for the token.
+        pub reserve: Balance,
+        /// Time the reserve was measured.
+        pub as_of: u64,
+        /// Audit report.
+        pub document: Document,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub enum RedemptionStatus {
+        Pending,
+        Paid,
+        Rejected,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Redemption {
+        pub holder: AccountId,
+        /// Tokens locked until the request is paid or rejected.
+        pub amount: Balance,
+        /// Hash of the holder's bank account reference.
+        pub bank_reference: Hash,
+        pub status: RedemptionStatus,
+        pub requested_at: u64,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct LimitTier {
+        /// Largest outflow within any rolling day.
+        pub daily: Balance,
+        /// Largest outflow within any rolling 30 days.
+        pub monthly: Balance,
+    }
+
+    /// Outflow counted against an account's limit, in hourly buckets for the
+    /// rolling day and daily buckets for the rolling 30 days. Buckets that left
+    /// their window are dropped, so each list stays bounded.
+    #[derive(Debug, Default, PartialEq, Eq, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Outflow {
+        /// (hour, value) buckets, oldest first.
+        pub hours: Vec<(u64, Balance)>,
+        /// (day, value) buckets, oldest first.
+        pub days: Vec<(u64, Balance)>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Hold {
+        pub payer: AccountId,
+        pub merchant: AccountId,
+        /// Largest amount the merchant may capture.
+        pub amount: Balance,
+        /// Time after which the hold can no longer be captured.
+        pub expires_at: u64,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Copy, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Subscription {
+        pub subscriber: AccountId,
+        pub merchant: AccountId,
+        /// Amount the merchant may collect once per period.
+        pub amount: Balance,
+        /// Length of a billing period, in milliseconds.
+        pub period: u64,
+        pub started_at: u64,
+        /// Time after which no new period starts.
+        pub ends_at: Option<u64>,
+        /// Number of periods collected or lapsed so far.
+        pub collected: u64,
+        /// Transfer of the next period waiting for review, counted once approved.
+        pub pending: Option<u64>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Clone, scale::Encode, scale::Decode, PackedLayout, SpreadLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct PendingTransfer {
+        pub from: AccountId,
+        pub to: AccountId,
+        /// Tokens locked from the sender until the review.
+        pub value: Balance,
+        pub requested_at: u64,
+        /// Spender whose allowance is given back if the transfer is rejected.
+        pub spender: Option<AccountId>,
+        /// Subscription whose period the transfer collects.
+        pub subscription: Option<u64>,
+        /// Payment reference, emitted once the transfer is approved.
+        pub reference: Option<Vec<u8>>,
+        /// Whether approval takes the transfer fee. Escrow and hold payouts
+        /// settled their fees already.
+        pub charge_fee: bool,
+    }
+
     #[ink(storage)]
     pub struct StableCurrency {
         ///Owner of Contract.
@@ -33,6 +216,103 @@ mod stable_currency {
         symbol: String,
         ///ecrow balance
         escrow_balances: StorageHashMap<(AccountId, AccountId), Balance>,
+        /// Accounts holding the minter role.
+        minters: StorageHashMap<AccountId, bool>,
+        /// Amount each minter may still mint.
+        minter_allowances: StorageHashMap<AccountId, Balance>,
+        /// Collateralized vault of each account.
+        vaults: StorageHashMap<AccountId, Vault>,
+        /// Price of native SEL in stable units, scaled by `PRICE_PRECISION`,
+        /// used only while no price oracle is set.
+        collateral_price: Lazy<Balance>,
+        /// Price oracle reporting native SEL in stable units, scaled by `PRICE_PRECISION`.
+        price_oracle: Lazy<Option<AccountId>>,
+        /// Minimum collateral value to debt, in percent.
+        collateral_ratio: Lazy<u32>,
+        /// Extra collateral a liquidator receives on top of the repaid debt, in percent.
+        liquidation_penalty: Lazy<u32>,
+        /// Escrow fee rate, in basis points.
+        fee_bps: Lazy<u32>,
+        /// Lowest escrow fee charged per payment.
+        fee_min: Lazy<Balance>,
+        /// Highest escrow fee charged per payment.
+        fee_max: Lazy<Balance>,
+        /// Fee reserved with each escrow, paid out on completion.
+        escrow_fees: StorageHashMap<(AccountId, AccountId), Balance>,
+        /// Transfer fee rate, in basis points. Zero disables the fee.
+        transfer_fee_bps: Lazy<u32>,
+        /// Highest transfer fee charged per transfer.
+        transfer_fee_cap: Lazy<Balance>,
+        /// Account receiving transfer and escrow fees.
+        treasury: Lazy<AccountId>,
+        /// Senders that pay no transfer or escrow fee.
+        fee_exempt_senders: StorageHashMap<AccountId, bool>,
+        /// Recipients that receive transfers and escrow payments without fee.
+        fee_exempt_recipients: StorageHashMap<AccountId, bool>,
+        /// System contracts, exempt from fees on either side.
+        system_accounts: StorageHashMap<AccountId, bool>,
+        /// Accounts holding the compliance role.
+        compliance_officers: StorageHashMap<AccountId, bool>,
+        /// Sanctioned accounts that can neither send nor receive.
+        blacklist: StorageHashMap<AccountId, bool>,
+        /// Accounts allowed to publish reserve attestations.
+        auditors: StorageHashMap<AccountId, bool>,
+        /// Reserve attestations by publishing index.
+        attestations: StorageHashMap<u64, ReserveAttestation>,
+        /// Number of attestations published, the latest one is at `attestation_count - 1`.
+        attestation_count: Lazy<u64>,
+        /// Block minting beyond the latest attested reserve.
+        reserve_enforced: Lazy<bool>,
+        /// Accounts allowed to settle redemption requests.
+        redemption_operators: StorageHashMap<AccountId, bool>,
+        /// Fiat redemption requests by id.
+        redemptions: StorageHashMap<u64, Redemption>,
+        /// Redemption request ids of each holder.
+        redemptions_of: StorageHashMap<AccountId, Vec<u64>>,
+        /// Id of the next redemption request.
+        next_redemption_id: Lazy<u64>,
+        /// Outflow limits by tier. Tier 0 applies to accounts without a tier,
+        /// a tier that is not configured is unlimited. The treasury and system
+        /// accounts are never limited.
+        limit_tiers: StorageHashMap<u8, LimitTier>,
+        /// Limit tier assigned to each account.
+        account_tiers: StorageHashMap<AccountId, u8>,
+        /// Outflow of each account within the rolling day and 30 days.
+        outflows: StorageHashMap<AccountId, Outflow>,
+        /// Pre-authorization holds by id.
+        holds: StorageHashMap<u64, Hold>,
+        /// Id of the next hold.
+        next_hold_id: Lazy<u64>,
+        /// Interest per second paid to savings, scaled by `INTEREST_PRECISION`.
+        savings_rate: Lazy<Balance>,
+        /// Value of one savings share, scaled by `INTEREST_PRECISION`.
+        savings_index: Lazy<Balance>,
+        /// Time interest was last accrued to the savings pool.
+        savings_updated_at: Lazy<u64>,
+        /// Savings shares of each account.
+        savings_shares: StorageHashMap<AccountId, Balance>,
+        /// Total savings shares of all accounts.
+        total_savings_shares: Lazy<Balance>,
+        /// Tokens held for savers, deposits plus accrued interest.
+        savings_pool: Lazy<Balance>,
+        /// Transfers above this value wait for compliance review.
+        review_threshold: Lazy<Balance>,
+        /// Transfers waiting for compliance review by id.
+        pending_transfers: StorageHashMap<u64, PendingTransfer>,
+        /// Id of the next pending transfer.
+        next_pending_id: Lazy<u64>,
+        /// Largest negative balance each account may run.
+        credit_limits: StorageHashMap<AccountId, Balance>,
+        /// Drawn credit of each account, including accrued interest.
+        credit_used: StorageHashMap<AccountId, Balance>,
+        /// Time interest was last accrued on each account's credit.
+        credit_accrued_at: StorageHashMap<AccountId, u64>,
+        /// Interest per second charged on drawn credit, scaled by `INTEREST_PRECISION`.
+        credit_rate: Lazy<Balance>,
+        /// Recurring payments by id, removed when cancelled.
+        subscriptions: StorageHashMap<u64, Subscription>,
+        /// Id of the next subscription.
+        next_subscription_id: Lazy<u64>,
     }
 
     #[ink(event)]
@@ -56,6 +336,350 @@ mod stable_currency {
         value: Balance,
     }
 
+    #[ink(event)]
+    pub struct Mint {
+        #[ink(topic)]
+        minter: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Burn {
+        #[ink(topic)]
+        burner: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct MinterAllowance {
+        #[ink(topic)]
+        minter: AccountId,
+        allowance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct VaultUpdated {
+        #[ink(topic)]
+        owner: AccountId,
+        collateral: Balance,
+        debt: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Liquidation {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        liquidator: AccountId,
+        debt: Balance,
+        seized: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CollateralPriceUpdated {
+        price: Balance,
+    }
+
+    #[ink(event)]
+    pub struct PriceOracleUpdated {
+        oracle: Option<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct CollateralRatioUpdated {
+        ratio: u32,
+    }
+
+    #[ink(event)]
+    pub struct LiquidationPenaltyUpdated {
+        penalty: u32,
+    }
+
+    #[ink(event)]
+    pub struct FeeScheduleUpdated {
+        bps: u32,
+        min: Balance,
+        max: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TransferFeeUpdated {
+        bps: u32,
+        cap: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TreasuryUpdated {
+        #[ink(topic)]
+        treasury: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct FeeExemptionUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        as_sender: bool,
+        as_recipient: bool,
+        system: bool,
+    }
+
+    /// Event emitted when a transfer fee is taken, `value` is the gross amount
+    /// and the recipient is credited `value - fee`.
+    #[ink(event)]
+    pub struct FeeCharged {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+        fee: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ComplianceOfficerUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct BlacklistUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        officer: AccountId,
+        blacklisted: bool,
+    }
+
+    /// Event emitted when funds are confiscated under `reference`, e.g. hash of the court order.
+    #[ink(event)]
+    pub struct Seized {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        #[ink(topic)]
+        reference: Hash,
+        officer: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AuditorUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct ReserveAttested {
+        #[ink(topic)]
+        auditor: AccountId,
+        #[ink(topic)]
+        doc_hash: Hash,
+        reserve: Balance,
+        as_of: u64,
+    }
+
+    #[ink(event)]
+    pub struct ReserveEnforcementUpdated {
+        enforced: bool,
+    }
+
+    #[ink(event)]
+    pub struct RedemptionOperatorUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct RedemptionRequested {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        holder: AccountId,
+        amount: Balance,
+        bank_reference: Hash,
+    }
+
+    #[ink(event)]
+    pub struct RedemptionSettled {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        operator: AccountId,
+        status: RedemptionStatus,
+    }
+
+    #[ink(event)]
+    pub struct LimitTierUpdated {
+        #[ink(topic)]
+        tier: u8,
+        daily: Balance,
+        monthly: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AccountTierUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        tier: u8,
+    }
+
+    #[ink(event)]
+    pub struct HoldAuthorized {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        payer: AccountId,
+        #[ink(topic)]
+        merchant: AccountId,
+        amount: Balance,
+        expires_at: u64,
+    }
+
+    /// Event emitted when a hold is captured, `released` is returned to the payer.
+    #[ink(event)]
+    pub struct HoldCaptured {
+        #[ink(topic)]
+        id: u64,
+        captured: Balance,
+        released: Balance,
+    }
+
+    #[ink(event)]
+    pub struct HoldVoided {
+        #[ink(topic)]
+        id: u64,
+        released: Balance,
+    }
+
+    /// Event emitted for a payment carrying a merchant reference, e.g. invoice number.
+    #[ink(event)]
+    pub struct Payment {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        #[ink(topic)]
+        reference: Vec<u8>,
+        value: Balance,
+    }
+
+    /// Event emitted when a payment carrying a merchant reference is locked in escrow.
+    #[ink(event)]
+    pub struct PaymentEscrowed {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        #[ink(topic)]
+        reference: Vec<u8>,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SavingsRateUpdated {
+        rate: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SavingsDeposited {
+        #[ink(topic)]
+        account: AccountId,
+        value: Balance,
+        shares: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SavingsWithdrawn {
+        #[ink(topic)]
+        account: AccountId,
+        value: Balance,
+        shares: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ReviewThresholdUpdated {
+        threshold: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TransferQueued {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TransferReviewed {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        officer: AccountId,
+        approved: bool,
+    }
+
+    #[ink(event)]
+    pub struct CreditLimitUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        limit: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CreditRateUpdated {
+        rate: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CreditDrawn {
+        #[ink(topic)]
+        account: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct CreditRepaid {
+        #[ink(topic)]
+        account: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SubscriptionCreated {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        subscriber: AccountId,
+        #[ink(topic)]
+        merchant: AccountId,
+        amount: Balance,
+        period: u64,
+        ends_at: Option<u64>,
+    }
+
+    #[ink(event)]
+    pub struct SubscriptionCollected {
+        #[ink(topic)]
+        id: u64,
+        period: u64,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SubscriptionCancelled {
+        #[ink(topic)]
+        id: u64,
+    }
+
     impl StableCurrency {
         #[ink(constructor)]
         pub fn new(initial_supply: Balance, symbol: String) -> Self {
@@ -69,7 +693,54 @@ mod stable_currency {
                 escrow_balances: StorageHashMap::new(),
                 allowances: StorageHashMap::new(),
                 balances,
-                symbol
+                symbol,
+                minters: StorageHashMap::new(),
+                minter_allowances: StorageHashMap::new(),
+                vaults: StorageHashMap::new(),
+                collateral_price: Lazy::new(0),
+                price_oracle: Lazy::new(None),
+                collateral_ratio: Lazy::new(150),
+                liquidation_penalty: Lazy::new(10),
+                fee_bps: Lazy::new(100),
+                fee_min: Lazy::new(1),
+                fee_max: Lazy::new(Balance::MAX),
+                escrow_fees: StorageHashMap::new(),
+                transfer_fee_bps: Lazy::new(0),
+                transfer_fee_cap: Lazy::new(Balance::MAX),
+                treasury: Lazy::new(caller),
+                fee_exempt_senders: StorageHashMap::new(),
+                fee_exempt_recipients: StorageHashMap::new(),
+                system_accounts: StorageHashMap::new(),
+                compliance_officers: StorageHashMap::new(),
+                blacklist: StorageHashMap::new(),
+                auditors: StorageHashMap::new(),
+                attestations: StorageHashMap::new(),
+                attestation_count: Lazy::new(0),
+                reserve_enforced: Lazy::new(false),
+                redemption_operators: StorageHashMap::new(),
+                redemptions: StorageHashMap::new(),
+                redemptions_of: StorageHashMap::new(),
+                next_redemption_id: Lazy::new(0),
+                limit_tiers: StorageHashMap::new(),
+                account_tiers: StorageHashMap::new(),
+                outflows: StorageHashMap::new(),
+                holds: StorageHashMap::new(),
+                next_hold_id: Lazy::new(0),
+                savings_rate: Lazy::new(0),
+                savings_index: Lazy::new(INTEREST_PRECISION),
+                savings_updated_at: Lazy::new(Self::env().block_timestamp()),
+                savings_shares: StorageHashMap::new(),
+                total_savings_shares: Lazy::new(0),
+                savings_pool: Lazy::new(0),
+                review_threshold: Lazy::new(Balance::MAX),
+                pending_transfers: StorageHashMap::new(),
+                next_pending_id: Lazy::new(0),
+                credit_limits: StorageHashMap::new(),
+                credit_used: StorageHashMap::new(),
+                credit_accrued_at: StorageHashMap::new(),
+                credit_rate: Lazy::new(0),
+                subscriptions: StorageHashMap::new(),
+                next_subscription_id: Lazy::new(0),
             }
         }
 
@@ -92,6 +763,9 @@ mod stable_currency {
         pub fn approve(&mut self, spender: AccountId, value: Balance) -> bool {
             // Record the new allowance.
             let owner = self.env().caller();
+            if self.is_blacklisted(owner) || self.is_blacklisted(spender) {
+                return false;
+            }
             self.allowances.insert((owner, spender), value);
 
             // Notify offchain users of the approval and report success.
@@ -108,30 +782,67 @@ mod stable_currency {
             self.allowance_of_or_zero(&owner, &spender)
         }
 
+        ///Transfer from `from` using the caller's allowance. Returns the pending
+        ///transfer id when it is queued for review, None once settled.
         #[ink(message)]
         pub fn transfer_from(
             &mut self,
             from: AccountId,
             to: AccountId,
             value: Balance,
-        ) -> Result<()> {
+        ) -> Result<Option<u64>> {
             // Ensure that a sufficient allowance exists.
             let caller = self.env().caller();
+            self.not_blacklisted(&caller)?;
             let allowance = self.allowance_of_or_zero(&from, &caller);
             if allowance < value {
                 return Err(Error::InsufficientAllowance);
             }
 
-            self.transfer_from_to(from, to, value)?;
+            let queued = self.transfer_from_to(from, to, value)?;
+            if let Some(id) = queued {
+                if let Some(pending) = self.pending_transfers.get_mut(&id) {
+                    pending.spender = Some(caller);
+                }
+            }
             self.allowances.insert((from, caller), allowance - value);
-            Ok(())
+            Ok(queued)
         }
 
+        ///Transfer from the caller. Returns the pending transfer id when it is
+        ///queued for review, None once settled.
         #[ink(message)]
-        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
+        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<Option<u64>> {
             self.transfer_from_to(self.env().caller(), to, value)
         }
 
+        ///Transfer with a reference the receiver can match against an invoice.
+        ///A transfer queued for review emits its `Payment` once approved.
+        #[ink(message)]
+        pub fn transfer_with_reference(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            reference: Vec<u8>,
+        ) -> Result<Option<u64>> {
+            let from = self.env().caller();
+            Self::valid_reference(&reference)?;
+            if let Some(id) = self.transfer_from_to(from, to, value)? {
+                if let Some(pending) = self.pending_transfers.get_mut(&id) {
+                    pending.reference = Some(reference);
+                }
+                return Ok(Some(id));
+            }
+
+            self.env().emit_event(Payment {
+                from,
+                to,
+                reference,
+                value,
+            });
+            Ok(None)
+        }
+
         #[ink(message)]
         pub fn transfer_ownership(&mut self, to: AccountId) -> Result<()> {
             self.only_owner(self.env().caller())?;
@@ -143,10 +854,10 @@ mod stable_currency {
         pub fn inc_supply(&mut self, value: Balance) -> Result<()> {
             let caller = self.env().caller();
             self.only_owner(caller)?;
+            self.within_reserve(value)?;
 
-            let owner_balance = self.balance_of_or_zero(&caller);
             *self.total_supply += value;
-            self.balances.insert(caller, owner_balance + value);
+            self.credit_account(caller, value);
 
             Ok(())
         }
@@ -167,105 +878,1677 @@ mod stable_currency {
             Ok(())
         }
 
+        ///Grant the minter role, or change the allowance of an existing minter.
         #[ink(message)]
-        pub fn create_payment(&mut self, seller: AccountId, value: u64) -> Result<()> {
-            let order = self.env().caller();
-            let fee = self.cal_fee(value);
-            let value = (value.checked_add(fee).unwrap_or(0)) as Balance;
-
-            let order_balance = self.balance_of_or_zero(&order);
-            if order_balance < value {
-                return Err(Error::InsufficientBalance);
-            }
-            self.balances.insert(order, order_balance - value);
+        pub fn set_minter(&mut self, minter: AccountId, allowance: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.minters.insert(minter, true);
+            self.minter_allowances.insert(minter, allowance);
 
-            let escrow_balance = self.escrow_of_or_zero(&order, &seller);
-            self.escrow_balances
-                .insert((order, seller), escrow_balance + value);
+            self.env().emit_event(MinterAllowance { minter, allowance });
             Ok(())
         }
 
         #[ink(message)]
-        pub fn complete_payment(&mut self, from: AccountId, to: AccountId) -> Result<()> {
-            let caller = self.env().caller();
-
-            if caller.clone() == from || caller.clone() == *self.owner {
-                let esbalance = self.escrow_of_or_zero(&from, &to);
-                let fee = (self.cal_fee(esbalance as u64)) as Balance;
-                let esbalance = esbalance - fee;
-                
-                let tobalance = self.balance_of_or_zero(&to);
-                self.balances.insert(to, esbalance + tobalance);
-                
-                let callbalance = self.balance_of_or_zero(&caller.clone());
-                self.balances.insert(caller, fee + callbalance);
-                
-                self.escrow_balances.insert((from, to), 0);
+        pub fn remove_minter(&mut self, minter: AccountId) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.minters.insert(minter, false);
+            self.minter_allowances.insert(minter, 0);
 
-                Ok(())
-            } else {
-                Err(Error::NotPermission)
-            }
+            self.env().emit_event(MinterAllowance { minter, allowance: 0 });
+            Ok(())
         }
 
         #[ink(message)]
-        pub fn refund(&mut self, from: AccountId, to: AccountId) -> Result<()> {
-            let caller = self.env().caller();
-            let esbalance = self.escrow_of_or_zero(&from, &to);
+        pub fn is_minter(&self, account: AccountId) -> bool {
+            *self.minters.get(&account).unwrap_or(&false)
+        }
+
+        #[ink(message)]
+        pub fn minter_allowance(&self, minter: AccountId) -> Balance {
+            *self.minter_allowances.get(&minter).unwrap_or(&0)
+        }
+
+        ///Mint to any account, drawing down the caller's minter allowance.
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let minter = self.env().caller();
+            if !self.is_minter(minter) {
+                return Err(Error::NotPermission);
+            }
+
+            self.not_blacklisted(&to)?;
+
+            let allowance = self.minter_allowance(minter);
+            if allowance < value {
+                return Err(Error::MintAllowanceExceeded);
+            }
+            self.within_reserve(value)?;
+            self.minter_allowances.insert(minter, allowance - value);
+
+            *self.total_supply += value;
+            self.credit_account(to, value);
+
+            self.env().emit_event(Mint { minter, to, value });
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(to),
+                value,
+            });
+            Ok(())
+        }
+
+        ///Burn from the caller's own balance, only by minter.
+        #[ink(message)]
+        pub fn burn(&mut self, value: Balance) -> Result<()> {
+            let burner = self.env().caller();
+            if !self.is_minter(burner) {
+                return Err(Error::NotPermission);
+            }
+            self.not_blacklisted(&burner)?;
+
+            let burner_balance = self.balance_of_or_zero(&burner);
+            if burner_balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            *self.total_supply -= value;
+            self.balances.insert(burner, burner_balance - value);
+
+            self.env().emit_event(Burn { burner, value });
+            self.env().emit_event(Transfer {
+                from: Some(burner),
+                to: None,
+                value,
+            });
+            Ok(())
+        }
+
+        ///Lock the transferred native SEL as collateral, opening the vault or topping it up.
+        #[ink(message, payable)]
+        pub fn open_vault(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            self.not_blacklisted(&caller)?;
+            let mut vault = self.vault_of(caller);
+            vault.collateral += self.env().transferred_balance();
+            self.update_vault(caller, vault);
+            Ok(())
+        }
+
+        ///Mint against the caller's vault while it stays above the collateral ratio.
+        #[ink(message)]
+        pub fn mint_from_vault(&mut self, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.not_blacklisted(&caller)?;
+            let price = self.price_of_collateral()?;
+
+            let mut vault = self.vault_of(caller);
+            vault.debt = vault.debt.checked_add(value).ok_or(Error::Undercollateralized)?;
+            if !Self::is_safe_at(&vault, price, *self.collateral_ratio) {
+                return Err(Error::Undercollateralized);
+            }
+            self.within_reserve(value)?;
+            self.update_vault(caller, vault);
+
+            *self.total_supply += value;
+            self.credit_account(caller, value);
+
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(caller),
+                value,
+            });
+            Ok(())
+        }
+
+        ///Burn stable currency from the caller to pay down the vault debt.
+        #[ink(message)]
+        pub fn repay(&mut self, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.not_blacklisted(&caller)?;
+            let mut vault = self.vault_of(caller);
+            let value = core::cmp::min(value, vault.debt);
+
+            let balance = self.balance_of_or_zero(&caller);
+            if balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            *self.total_supply -= value;
+            self.balances.insert(caller, balance - value);
+
+            vault.debt -= value;
+            self.update_vault(caller, vault);
+
+            self.env().emit_event(Transfer {
+                from: Some(caller),
+                to: None,
+                value,
+            });
+            Ok(())
+        }
+
+        ///Unlock native SEL from the vault as long as the remaining debt stays covered.
+        #[ink(message)]
+        pub fn withdraw_collateral(&mut self, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self.not_blacklisted(&caller)?;
+            let mut vault = self.vault_of(caller);
+            if vault.collateral < value {
+                return Err(Error::InsufficientBalance);
+            }
+
+            vault.collateral -= value;
+            if !self.is_safe(&vault) {
+                return Err(Error::Undercollateralized);
+            }
+            self.update_vault(caller, vault);
+            if self.env().transfer(caller, value).is_err() {
+                vault.collateral += value;
+                self.update_vault(caller, vault);
+                return Err(Error::TransferFailed);
+            }
+            Ok(())
+        }
+
+        ///Repay the whole debt of an undercollateralized vault and take its collateral
+        ///worth the debt plus the liquidation penalty.
+        #[ink(message)]
+        pub fn liquidate(&mut self, owner: AccountId) -> Result<()> {
+            let liquidator = self.env().caller();
+            self.not_blacklisted(&liquidator)?;
+            let price = self.price_of_collateral()?;
+            let mut vault = self.vault_of(owner);
+            if vault.debt == 0 || Self::is_safe_at(&vault, price, *self.collateral_ratio) {
+                return Err(Error::VaultNotLiquidatable);
+            }
+
+            let balance = self.balance_of_or_zero(&liquidator);
+            if balance < vault.debt {
+                return Err(Error::InsufficientBalance);
+            }
+            let debt = vault.debt;
+            let seized = debt
+                .saturating_mul(PRICE_PRECISION)
+                .saturating_mul(100 + *self.liquidation_penalty as Balance)
+                / price.saturating_mul(100);
+            let seized = core::cmp::min(seized, vault.collateral);
+
+            let before = vault;
+            *self.total_supply -= debt;
+            self.balances.insert(liquidator, balance - debt);
+            vault.collateral -= seized;
+            vault.debt = 0;
+            self.update_vault(owner, vault);
+            if self.env().transfer(liquidator, seized).is_err() {
+                *self.total_supply += debt;
+                self.balances.insert(liquidator, balance);
+                self.update_vault(owner, before);
+                return Err(Error::TransferFailed);
+            }
+
+            self.env().emit_event(Transfer {
+                from: Some(liquidator),
+                to: None,
+                value: debt,
+            });
+            self.env().emit_event(Liquidation {
+                owner,
+                liquidator,
+                debt,
+                seized,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn vault_of(&self, owner: AccountId) -> Vault {
+            self.vaults.get(&owner).copied().unwrap_or_default()
+        }
+
+        ///get price of native SEL, from the price oracle when one is set, zero when unknown
+        #[ink(message)]
+        pub fn collateral_price(&self) -> Balance {
+            self.price_of_collateral().unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn price_oracle(&self) -> Option<AccountId> {
+            *self.price_oracle
+        }
+
+        #[ink(message)]
+        pub fn collateral_ratio(&self) -> u32 {
+            *self.collateral_ratio
+        }
+
+        #[ink(message)]
+        pub fn liquidation_penalty(&self) -> u32 {
+            *self.liquidation_penalty
+        }
+
+        ///Set fallback price of native SEL in stable units, scaled by 10^9, used
+        ///while no price oracle is set, only by owner.
+        #[ink(message)]
+        pub fn set_collateral_price(&mut self, price: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.collateral_price = price;
+            self.env().emit_event(CollateralPriceUpdated { price });
+            Ok(())
+        }
+
+        ///Set price oracle the collateral price is read from, None to use the fallback price, only by owner.
+        #[ink(message)]
+        pub fn set_price_oracle(&mut self, oracle: Option<AccountId>) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.price_oracle = oracle;
+            self.env().emit_event(PriceOracleUpdated { oracle });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_collateral_ratio(&mut self, ratio: u32) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.collateral_ratio = ratio;
+            self.env().emit_event(CollateralRatioUpdated { ratio });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_liquidation_penalty(&mut self, penalty: u32) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.liquidation_penalty = penalty;
+            self.env().emit_event(LiquidationPenaltyUpdated { penalty });
+            Ok(())
+        }
+
+        ///Set escrow fee rate in basis point, bounded by min and max fee, only by owner.
+        #[ink(message)]
+        pub fn set_fee_schedule(&mut self, bps: u32, min: Balance, max: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            if bps > 10_000 || min > max {
+                return Err(Error::InvalidFeeSchedule);
+            }
+            *self.fee_bps = bps;
+            *self.fee_min = min;
+            *self.fee_max = max;
+
+            self.env().emit_event(FeeScheduleUpdated { bps, min, max });
+            Ok(())
+        }
+
+        ///get escrow fee rate, min and max fee
+        #[ink(message)]
+        pub fn fee_schedule(&self) -> (u32, Balance, Balance) {
+            (*self.fee_bps, *self.fee_min, *self.fee_max)
+        }
+
+        ///Set transfer fee rate in basis point and its cap, only by owner.
+        #[ink(message)]
+        pub fn set_transfer_fee(&mut self, bps: u32, cap: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            if bps > 10_000 {
+                return Err(Error::InvalidFeeSchedule);
+            }
+            *self.transfer_fee_bps = bps;
+            *self.transfer_fee_cap = cap;
+
+            self.env().emit_event(TransferFeeUpdated { bps, cap });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_treasury(&mut self, treasury: AccountId) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.treasury = treasury;
+
+            self.env().emit_event(TreasuryUpdated { treasury });
+            Ok(())
+        }
+
+        ///Exempt an account from transfer and escrow fees when it sends, receives, or both.
+        #[ink(message)]
+        pub fn set_fee_exemption(
+            &mut self,
+            account: AccountId,
+            as_sender: bool,
+            as_recipient: bool,
+        ) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.fee_exempt_senders.insert(account, as_sender);
+            self.fee_exempt_recipients.insert(account, as_recipient);
+            self.emit_exemption(account);
+            Ok(())
+        }
+
+        ///Mark a system contract, which never pays nor causes transfer or escrow fees.
+        #[ink(message)]
+        pub fn set_system_account(&mut self, account: AccountId, system: bool) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.system_accounts.insert(account, system);
+            self.emit_exemption(account);
+            Ok(())
+        }
+
+        ///get transfer fee rate and cap
+        #[ink(message)]
+        pub fn transfer_fee(&self) -> (u32, Balance) {
+            (*self.transfer_fee_bps, *self.transfer_fee_cap)
+        }
+
+        #[ink(message)]
+        pub fn treasury(&self) -> AccountId {
+            *self.treasury
+        }
+
+        ///get whether account is fee exempt as sender, as recipient, and as system account
+        #[ink(message)]
+        pub fn fee_exemption(&self, account: AccountId) -> (bool, bool, bool) {
+            (
+                *self.fee_exempt_senders.get(&account).unwrap_or(&false),
+                *self.fee_exempt_recipients.get(&account).unwrap_or(&false),
+                self.is_system_account(&account),
+            )
+        }
+
+        ///get fee charged for transfer value from one account to another
+        #[ink(message)]
+        pub fn transfer_fee_of(&self, from: AccountId, to: AccountId, value: Balance) -> Balance {
+            if self.is_fee_exempt(&from, &to) {
+                return 0;
+            }
+            let fee = value.saturating_mul(*self.transfer_fee_bps as Balance) / 10_000;
+            core::cmp::min(fee, *self.transfer_fee_cap)
+        }
+
+        ///Grant or revoke the compliance role, only by owner.
+        #[ink(message)]
+        pub fn set_compliance_officer(&mut self, account: AccountId, allowed: bool) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.compliance_officers.insert(account, allowed);
+
+            self.env().emit_event(ComplianceOfficerUpdated { account, allowed });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_compliance_officer(&self, account: AccountId) -> bool {
+            *self.compliance_officers.get(&account).unwrap_or(&false)
+        }
+
+        #[ink(message)]
+        pub fn blacklist(&mut self, account: AccountId) -> Result<()> {
+            self.set_blacklisted(account, true)
+        }
+
+        #[ink(message)]
+        pub fn unblacklist(&mut self, account: AccountId) -> Result<()> {
+            self.set_blacklisted(account, false)
+        }
+
+        #[ink(message)]
+        pub fn is_blacklisted(&self, account: AccountId) -> bool {
+            *self.blacklist.get(&account).unwrap_or(&false)
+        }
+
+        ///Move funds out of an account without its consent, only by compliance officer.
+        ///`reference` identifies the order behind it, e.g. hash of the court order.
+        #[ink(message)]
+        pub fn seize(
+            &mut self,
+            account: AccountId,
+            value: Balance,
+            to: AccountId,
+            reference: Hash,
+        ) -> Result<()> {
+            let officer = self.env().caller();
+            self.only_compliance(officer)?;
+            self.not_blacklisted(&to)?;
+
+            let balance = self.balance_of_or_zero(&account);
+            if balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            self.balances.insert(account, balance - value);
+            self.credit_account(to, value);
+
+            self.env().emit_event(Transfer {
+                from: Some(account),
+                to: Some(to),
+                value,
+            });
+            self.env().emit_event(Seized {
+                account,
+                to,
+                reference,
+                officer,
+                value,
+            });
+            Ok(())
+        }
+
+        ///Grant or revoke the auditor role, only by owner.
+        #[ink(message)]
+        pub fn set_auditor(&mut self, account: AccountId, allowed: bool) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.auditors.insert(account, allowed);
+
+            self.env().emit_event(AuditorUpdated { account, allowed });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_auditor(&self, account: AccountId) -> bool {
+            *self.auditors.get(&account).unwrap_or(&false)
+        }
+
+        ///Publish fiat reserve measured at `as_of` with its audit report, only by auditor.
+        #[ink(message)]
+        pub fn attest_reserve(
+            &mut self,
+            reserve: Balance,
+            as_of: u64,
+            doc_uri: String,
+            doc_hash: Hash,
+        ) -> Result<()> {
+            let auditor = self.env().caller();
+            if !self.is_auditor(auditor) {
+                return Err(Error::NotPermission);
+            }
+            let latest_as_of = self.latest_attestation().map(|a| a.as_of).unwrap_or(0);
+            if as_of > self.env().block_timestamp() || as_of < latest_as_of {
+                return Err(Error::InvalidAttestation);
+            }
+
+            let index = *self.attestation_count;
+            *self.attestation_count += 1;
+            self.attestations.insert(index, ReserveAttestation {
+                auditor,
+                reserve,
+                as_of,
+                document: Document { doc_uri, doc_hash },
+            });
+            self.env().emit_event(ReserveAttested {
+                auditor,
+                doc_hash,
+                reserve,
+                as_of,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn latest_attestation(&self) -> Option<ReserveAttestation> {
+            self.attestation_count
+                .checked_sub(1)
+                .and_then(|index| self.attestation(index))
+        }
+
+        ///get attestation by its publishing index
+        #[ink(message)]
+        pub fn attestation(&self, index: u64) -> Option<ReserveAttestation> {
+            self.attestations.get(&index).cloned()
+        }
+
+        #[ink(message)]
+        pub fn attestation_count(&self) -> u64 {
+            *self.attestation_count
+        }
+
+        ///get latest attested reserve, total supply and whether the supply is fully backed
+        #[ink(message)]
+        pub fn reserve_coverage(&self) -> (Balance, Balance, bool) {
+            let reserve = self.attested_reserve();
+            (reserve, *self.total_supply, reserve >= *self.total_supply)
+        }
+
+        ///Block or allow minting beyond the latest attested reserve, only by owner.
+        #[ink(message)]
+        pub fn set_reserve_enforced(&mut self, enforced: bool) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.reserve_enforced = enforced;
+
+            self.env().emit_event(ReserveEnforcementUpdated { enforced });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn reserve_enforced(&self) -> bool {
+            *self.reserve_enforced
+        }
+
+        ///Grant or revoke the redemption operator role, only by owner.
+        #[ink(message)]
+        pub fn set_redemption_operator(&mut self, account: AccountId, allowed: bool) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.redemption_operators.insert(account, allowed);
+
+            self.env().emit_event(RedemptionOperatorUpdated { account, allowed });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_redemption_operator(&self, account: AccountId) -> bool {
+            *self.redemption_operators.get(&account).unwrap_or(&false)
+        }
+
+        ///Lock tokens of the caller to be redeemed for fiat, returns the request id.
+        #[ink(message)]
+        pub fn request_redemption(&mut self, amount: Balance, bank_reference_hash: Hash) -> Result<u64> {
+            let holder = self.env().caller();
+            self.not_blacklisted(&holder)?;
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let balance = self.balance_of_or_zero(&holder);
+            if balance < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            self.balances.insert(holder, balance - amount);
+
+            let id = *self.next_redemption_id;
+            *self.next_redemption_id += 1;
+            self.redemptions.insert(id, Redemption {
+                holder,
+                amount,
+                bank_reference: bank_reference_hash,
+                status: RedemptionStatus::Pending,
+                requested_at: self.env().block_timestamp(),
+            });
+            let mut ids = self.redemptions_of(holder);
+            ids.push(id);
+            self.redemptions_of.insert(holder, ids);
+
+            self.env().emit_event(RedemptionRequested {
+                id,
+                holder,
+                amount,
+                bank_reference: bank_reference_hash,
+            });
+            Ok(id)
+        }
+
+        ///Fiat has been paid out, burn the locked tokens.
+        #[ink(message)]
+        pub fn mark_redemption_paid(&mut self, id: u64) -> Result<()> {
+            let mut redemption = self.pending_redemption(id)?;
+            *self.total_supply -= redemption.amount;
+            redemption.status = RedemptionStatus::Paid;
+            self.settle_redemption(id, redemption);
+
+            self.env().emit_event(Transfer {
+                from: Some(redemption.holder),
+                to: None,
+                value: redemption.amount,
+            });
+            Ok(())
+        }
+
+        ///Reject the request and unlock the tokens back to the holder.
+        #[ink(message)]
+        pub fn reject_redemption(&mut self, id: u64) -> Result<()> {
+            let mut redemption = self.pending_redemption(id)?;
+            self.credit_account(redemption.holder, redemption.amount);
+            redemption.status = RedemptionStatus::Rejected;
+            self.settle_redemption(id, redemption);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn redemption(&self, id: u64) -> Option<Redemption> {
+            self.redemptions.get(&id).copied()
+        }
+
+        ///get redemption request ids of holder
+        #[ink(message)]
+        pub fn redemptions_of(&self, holder: AccountId) -> Vec<u64> {
+            match self.redemptions_of.get(&holder) {
+                None => Vec::new(),
+                Some(ids) => ids.clone(),
+            }
+        }
+
+        ///get redemption request ids with status among the ids from `start`, scanning
+        ///at most `limit` ids capped at 100; page on with `start + limit`
+        #[ink(message)]
+        pub fn redemptions_by_status(&self, status: RedemptionStatus, start: u64, limit: u64) -> Vec<u64> {
+            let end = start
+                .saturating_add(core::cmp::min(limit, MAX_PAGE_SIZE))
+                .min(*self.next_redemption_id);
+            (start..end)
+                .filter(|id| self.redemptions.get(id).map(|r| r.status) == Some(status))
+                .collect()
+        }
+
+        ///Set daily and monthly outflow limit of a tier, only by owner.
+        #[ink(message)]
+        pub fn set_limit_tier(&mut self, tier: u8, daily: Balance, monthly: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.limit_tiers.insert(tier, LimitTier { daily, monthly });
+
+            self.env().emit_event(LimitTierUpdated { tier, daily, monthly });
+            Ok(())
+        }
+
+        ///Remove limit of a tier, making it unlimited, only by owner.
+        #[ink(message)]
+        pub fn remove_limit_tier(&mut self, tier: u8) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.limit_tiers.take(&tier);
+
+            self.env().emit_event(LimitTierUpdated {
+                tier,
+                daily: Balance::MAX,
+                monthly: Balance::MAX,
+            });
+            Ok(())
+        }
+
+        ///Assign limit tier to account, by owner or compliance officer.
+        #[ink(message)]
+        pub fn assign_tier(&mut self, account: AccountId, tier: u8) -> Result<()> {
+            let caller = self.env().caller();
+            if caller != *self.owner && !self.is_compliance_officer(caller) {
+                return Err(Error::NotPermission);
+            }
+            self.account_tiers.insert(account, tier);
+
+            self.env().emit_event(AccountTierUpdated { account, tier });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn tier_of(&self, account: AccountId) -> u8 {
+            *self.account_tiers.get(&account).unwrap_or(&0)
+        }
+
+        #[ink(message)]
+        pub fn limit_tier(&self, tier: u8) -> Option<LimitTier> {
+            self.limit_tiers.get(&tier).copied()
+        }
+
+        ///get amount account can still send within the rolling day and 30 days
+        #[ink(message)]
+        pub fn remaining_limit(&self, account: AccountId) -> (Balance, Balance) {
+            let tier = match self.limit_of(&account) {
+                None => return (Balance::MAX, Balance::MAX),
+                Some(t) => t,
+            };
+            let (daily, monthly) = Self::outflow_totals(&self.current_outflow(&account));
+            (
+                tier.daily.saturating_sub(daily),
+                tier.monthly.saturating_sub(monthly),
+            )
+        }
+
+        ///Place a hold on the caller's funds for merchant, capturable for `duration` millisecond.
+        ///Returns the hold id.
+        #[ink(message)]
+        pub fn authorize(&mut self, merchant: AccountId, amount: Balance, duration: u64) -> Result<u64> {
+            let payer = self.env().caller();
+            self.not_blacklisted(&payer)?;
+            self.not_blacklisted(&merchant)?;
+
+            let balance = self.balance_of_or_zero(&payer);
+            if balance < amount {
+                return Err(Error::InsufficientBalance);
+            }
+            self.spend_limit(payer, amount)?;
+            self.balances.insert(payer, balance - amount);
+
+            let id = *self.next_hold_id;
+            *self.next_hold_id += 1;
+            let expires_at = self.env().block_timestamp().saturating_add(duration);
+            self.holds.insert(id, Hold {
+                payer,
+                merchant,
+                amount,
+                expires_at,
+            });
+
+            self.env().emit_event(HoldAuthorized {
+                id,
+                payer,
+                merchant,
+                amount,
+                expires_at,
+            });
+            Ok(id)
+        }
+
+        ///Merchant takes the final amount of an unexpired hold, the rest goes back to the payer.
+        ///A capture above the review threshold is queued for review and its pending
+        ///transfer id returned.
+        #[ink(message)]
+        pub fn capture(&mut self, id: u64, amount: Balance) -> Result<Option<u64>> {
+            let hold = self.hold(id).ok_or(Error::HoldNotFound)?;
+            if self.env().caller() != hold.merchant {
+                return Err(Error::NotPermission);
+            }
+            if self.env().block_timestamp() > hold.expires_at {
+                return Err(Error::HoldExpired);
+            }
+            if amount > hold.amount {
+                return Err(Error::CaptureExceedsHold);
+            }
+            self.not_blacklisted(&hold.payer)?;
+            self.not_blacklisted(&hold.merchant)?;
+            self.holds.take(&id);
+
+            let released = hold.amount - amount;
+            self.credit_account(hold.payer, released);
+            self.release_limit(hold.payer, released);
+
+            let queued = self.pay_out(hold.payer, hold.merchant, amount);
+            self.env().emit_event(HoldCaptured {
+                id,
+                captured: amount,
+                released,
+            });
+            Ok(queued)
+        }
+
+        ///Release a hold back to the payer, by merchant at any time, by payer once expired
+        ///or by compliance officer. Funds released to a blacklisted payer stay frozen there.
+        #[ink(message)]
+        pub fn void(&mut self, id: u64) -> Result<()> {
+            let hold = self.hold(id).ok_or(Error::HoldNotFound)?;
+            let caller = self.env().caller();
+            let expired = self.env().block_timestamp() > hold.expires_at;
+            if caller != hold.merchant
+                && !(caller == hold.payer && expired)
+                && !self.is_compliance_officer(caller)
+            {
+                return Err(Error::NotPermission);
+            }
+            self.not_blacklisted(&caller)?;
+            self.holds.take(&id);
+
+            self.credit_account(hold.payer, hold.amount);
+            self.release_limit(hold.payer, hold.amount);
+
+            self.env().emit_event(HoldVoided {
+                id,
+                released: hold.amount,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn hold(&self, id: u64) -> Option<Hold> {
+            self.holds.get(&id).copied()
+        }
+
+        ///Let merchant pull amount from the caller once every period until ends_at.
+        #[ink(message)]
+        pub fn subscribe(
+            &mut self,
+            merchant: AccountId,
+            amount: Balance,
+            period: u64,
+            ends_at: Option<u64>,
+        ) -> Result<u64> {
+            if period == 0 {
+                return Err(Error::InvalidPeriod);
+            }
+            let subscriber = self.env().caller();
+            self.not_blacklisted(&subscriber)?;
+            self.not_blacklisted(&merchant)?;
+
+            let id = *self.next_subscription_id;
+            *self.next_subscription_id += 1;
+            self.subscriptions.insert(id, Subscription {
+                subscriber,
+                merchant,
+                amount,
+                period,
+                started_at: self.env().block_timestamp(),
+                ends_at,
+                collected: 0,
+                pending: None,
+            });
+
+            self.env().emit_event(SubscriptionCreated {
+                id,
+                subscriber,
+                merchant,
+                amount,
+                period,
+                ends_at,
+            });
+            Ok(id)
+        }
+
+        ///Merchant collects the next period of a subscription once that period has started.
+        ///Each call collects the current period only, periods missed entirely lapse
+        ///instead of being caught up. It is paid from the subscriber's balance only,
+        ///never from credit, and a collection queued for review counts once approved.
+        #[ink(message)]
+        pub fn collect(&mut self, id: u64) -> Result<()> {
+            let mut subscription = self.subscription(id).ok_or(Error::SubscriptionNotFound)?;
+            if self.env().caller() != subscription.merchant {
+                return Err(Error::NotPermission);
+            }
+            if subscription.pending.is_some() {
+                return Err(Error::CollectionPending);
+            }
+            let now = self.env().block_timestamp();
+            let current = now.saturating_sub(subscription.started_at) / subscription.period;
+            subscription.collected = core::cmp::max(subscription.collected, current);
+            let due_at = subscription.period
+                .saturating_mul(subscription.collected)
+                .saturating_add(subscription.started_at);
+            if now < due_at
+                || subscription.ends_at.map_or(false, |ends_at| due_at > ends_at)
+            {
+                return Err(Error::PaymentNotDue);
+            }
+            if self.balance_of_or_zero(&subscription.subscriber) < subscription.amount {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let queued =
+                self.transfer_from_to(subscription.subscriber, subscription.merchant, subscription.amount)?;
+            if let Some(pending_id) = queued {
+                if let Some(pending) = self.pending_transfers.get_mut(&pending_id) {
+                    pending.subscription = Some(id);
+                }
+                subscription.pending = Some(pending_id);
+                self.subscriptions.insert(id, subscription);
+                return Ok(());
+            }
+            self.count_collected(id, subscription);
+            Ok(())
+        }
+
+        ///Cancel a subscription, by subscriber or merchant at any time. A collection
+        ///still waiting for review is voided and refunded to the subscriber.
+        #[ink(message)]
+        pub fn cancel_subscription(&mut self, id: u64) -> Result<()> {
+            let subscription = self.subscription(id).ok_or(Error::SubscriptionNotFound)?;
+            let caller = self.env().caller();
+            if caller != subscription.subscriber && caller != subscription.merchant {
+                return Err(Error::NotPermission);
+            }
+            self.subscriptions.take(&id);
+            if let Some(pending_id) = subscription.pending {
+                if let Some(pending) = self.pending_transfers.take(&pending_id) {
+                    self.revert_pending(&pending);
+                }
+            }
+
+            self.env().emit_event(SubscriptionCancelled { id });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn subscription(&self, id: u64) -> Option<Subscription> {
+            self.subscriptions.get(&id).copied()
+        }
+
+        ///Set interest per second paid to savings scaled by 10^12, only by owner.
+        #[ink(message)]
+        pub fn set_savings_rate(&mut self, rate: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.drip();
+            *self.savings_rate = rate;
+
+            self.env().emit_event(SavingsRateUpdated { rate });
+            Ok(())
+        }
+
+        ///Move tokens from the caller's balance into savings.
+        #[ink(message)]
+        pub fn deposit_savings(&mut self, value: Balance) -> Result<()> {
+            let account = self.env().caller();
+            self.not_blacklisted(&account)?;
+            let balance = self.balance_of_or_zero(&account);
+            if balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            self.drip();
+
+            let shares = value.saturating_mul(INTEREST_PRECISION) / *self.savings_index;
+            if shares == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            self.balances.insert(account, balance - value);
+            *self.savings_pool += value;
+            let account_shares = self.savings_shares_of(account);
+            self.savings_shares.insert(account, account_shares + shares);
+            *self.total_savings_shares += shares;
+
+            self.env().emit_event(SavingsDeposited { account, value, shares });
+            Ok(())
+        }
+
+        ///Move tokens with the interest earned from savings back to the caller's balance.
+        #[ink(message)]
+        pub fn withdraw_savings(&mut self, value: Balance) -> Result<()> {
+            let account = self.env().caller();
+            self.not_blacklisted(&account)?;
+            self.drip();
+
+            let index = *self.savings_index;
+            // Round shares up so savers never withdraw more than they own.
+            let shares = value
+                .checked_mul(INTEREST_PRECISION)
+                .and_then(|scaled| scaled.checked_add(index - 1))
+                .ok_or(Error::InsufficientBalance)?
+                / index;
+            let account_shares = self.savings_shares_of(account);
+            if account_shares < shares || *self.savings_pool < value {
+                return Err(Error::InsufficientBalance);
+            }
+            self.savings_shares.insert(account, account_shares - shares);
+            *self.total_savings_shares -= shares;
+            *self.savings_pool -= value;
+            self.credit_account(account, value);
+
+            self.env().emit_event(SavingsWithdrawn { account, value, shares });
+            Ok(())
+        }
+
+        ///get savings of account including interest accrued until now
+        #[ink(message)]
+        pub fn savings_balance(&self, account: AccountId) -> Balance {
+            self.savings_shares_of(account).saturating_mul(self.current_savings_index()) / INTEREST_PRECISION
+        }
+
+        #[ink(message)]
+        pub fn savings_shares_of(&self, account: AccountId) -> Balance {
+            *self.savings_shares.get(&account).unwrap_or(&0)
+        }
+
+        #[ink(message)]
+        pub fn savings_rate(&self) -> Balance {
+            *self.savings_rate
+        }
+
+        #[ink(message)]
+        pub fn savings_pool(&self) -> Balance {
+            *self.savings_pool
+        }
+
+        ///Set value above which transfers wait for compliance review, only by owner.
+        #[ink(message)]
+        pub fn set_review_threshold(&mut self, threshold: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            *self.review_threshold = threshold;
+
+            self.env().emit_event(ReviewThresholdUpdated { threshold });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn review_threshold(&self) -> Balance {
+            *self.review_threshold
+        }
+
+        ///Settle a pending transfer, only by compliance officer.
+        #[ink(message)]
+        pub fn approve_transfer(&mut self, id: u64) -> Result<()> {
+            let pending = self.review_pending_transfer(id)?;
+            self.not_blacklisted(&pending.from)?;
+            self.not_blacklisted(&pending.to)?;
+            self.pending_transfers.take(&id);
+            self.settle_transfer(pending.from, pending.to, pending.value, pending.charge_fee);
+            if let Some(reference) = pending.reference {
+                self.env().emit_event(Payment {
+                    from: pending.from,
+                    to: pending.to,
+                    reference,
+                    value: pending.value,
+                });
+            }
+            if let Some(subscription_id) = pending.subscription {
+                if let Some(subscription) = self.subscription(subscription_id) {
+                    self.count_collected(subscription_id, subscription);
+                }
+            }
+
+            self.env().emit_event(TransferReviewed {
+                id,
+                officer: self.env().caller(),
+                approved: true,
+            });
+            Ok(())
+        }
+
+        ///Unlock a pending transfer back to the sender, giving back the allowance and
+        ///outflow limit it used, only by compliance officer. Credit drawn for it is repaid first.
+        #[ink(message)]
+        pub fn reject_transfer(&mut self, id: u64) -> Result<()> {
+            let pending = self.review_pending_transfer(id)?;
+            self.pending_transfers.take(&id);
+            self.revert_pending(&pending);
+            if let Some(subscription_id) = pending.subscription {
+                if let Some(mut subscription) = self.subscription(subscription_id) {
+                    subscription.pending = None;
+                    self.subscriptions.insert(subscription_id, subscription);
+                }
+            }
+
+            self.env().emit_event(TransferReviewed {
+                id,
+                officer: self.env().caller(),
+                approved: false,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn pending_transfer(&self, id: u64) -> Option<PendingTransfer> {
+            self.pending_transfers.get(&id).cloned()
+        }
+
+        ///get ids of transfers waiting for review
+        #[ink(message)]
+        pub fn pending_transfer_ids(&self) -> Vec<u64> {
+            let mut ids: Vec<u64> = self.pending_transfers.keys().copied().collect();
+            ids.sort_unstable();
+            ids
+        }
+
+        ///Let account overdraw its balance up to limit, only by owner.
+        #[ink(message)]
+        pub fn set_credit_limit(&mut self, account: AccountId, limit: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            self.accrue_credit(account);
+            self.credit_limits.insert(account, limit);
+
+            self.env().emit_event(CreditLimitUpdated { account, limit });
+            Ok(())
+        }
+
+        ///Set interest per second on drawn credit scaled by 10^12, only by owner.
+        ///Accrues at the old rate for open credit lines only, settled lines are
+        ///dropped from the borrower set once repaid.
+        #[ink(message)]
+        pub fn set_credit_rate(&mut self, rate: Balance) -> Result<()> {
+            self.only_owner(self.env().caller())?;
+            let borrowers: Vec<AccountId> = self.credit_used.keys().copied().collect();
+            for borrower in borrowers {
+                self.accrue_credit(borrower);
+            }
+            *self.credit_rate = rate;
+
+            self.env().emit_event(CreditRateUpdated { rate });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn credit_rate(&self) -> Balance {
+            *self.credit_rate
+        }
+
+        #[ink(message)]
+        pub fn credit_limit(&self, account: AccountId) -> Balance {
+            *self.credit_limits.get(&account).unwrap_or(&0)
+        }
+
+        ///get drawn credit of account including interest accrued until now
+        #[ink(message)]
+        pub fn credit_used(&self, account: AccountId) -> Balance {
+            let used = *self.credit_used.get(&account).unwrap_or(&0);
+            used + self.pending_credit_interest(&account, used)
+        }
+
+        #[ink(message)]
+        pub fn credit_available(&self, account: AccountId) -> Balance {
+            self.credit_limit(account).saturating_sub(self.credit_used(account))
+        }
+
+        ///Escrow value plus the escrow fee for seller, released by `complete_payment`.
+        #[ink(message)]
+        pub fn create_payment(
+            &mut self,
+            seller: AccountId,
+            value: Balance,
+            reference: Vec<u8>,
+        ) -> Result<()> {
+            let order = self.env().caller();
+            Self::valid_reference(&reference)?;
+            self.not_blacklisted(&order)?;
+            self.not_blacklisted(&seller)?;
+            let fee = if self.is_fee_exempt(&order, &seller) { 0 } else { self.cal_fee(value) };
+            let total = value.checked_add(fee).ok_or(Error::InsufficientBalance)?;
+
+            let order_balance = self.balance_of_or_zero(&order);
+            if order_balance < total {
+                return Err(Error::InsufficientBalance);
+            }
+            self.spend_limit(order, total)?;
+            self.balances.insert(order, order_balance - total);
+
+            let escrow_balance = self.escrow_of_or_zero(&order, &seller);
+            self.escrow_balances
+                .insert((order, seller), escrow_balance + total);
+            let escrow_fee = self.escrow_fee_of_or_zero(&order, &seller);
+            self.escrow_fees.insert((order, seller), escrow_fee + fee);
+
+            self.env().emit_event(PaymentEscrowed {
+                from: order,
+                to: seller,
+                reference,
+                value,
+            });
+            Ok(())
+        }
+
+        ///Release escrow to seller and its fee to the treasury. A payout above the
+        ///review threshold is queued for review and its pending transfer id returned,
+        ///the escrow fee is kept either way.
+        #[ink(message)]
+        pub fn complete_payment(&mut self, from: AccountId, to: AccountId) -> Result<Option<u64>> {
+            let caller = self.env().caller();
+
+            if caller.clone() == from || caller.clone() == *self.owner {
+                self.not_blacklisted(&from)?;
+                self.not_blacklisted(&to)?;
+                let esbalance = self.escrow_of_or_zero(&from, &to);
+                let fee = self.escrow_fee_of_or_zero(&from, &to);
+                let esbalance = esbalance - fee;
+                
+                self.credit_account(*self.treasury, fee);
+                
+                self.escrow_balances.insert((from, to), 0);
+                self.escrow_fees.insert((from, to), 0);
+
+                Ok(self.pay_out(from, to, esbalance))
+            } else {
+                Err(Error::NotPermission)
+            }
+        }
+
+        #[ink(message)]
+        pub fn refund(&mut self, from: AccountId, to: AccountId) -> Result<()> {
+            let caller = self.env().caller();
+            let esbalance = self.escrow_of_or_zero(&from, &to);
 
             if caller.clone() == to || caller.clone() == *self.owner {
-                let balance = self.balance_of_or_zero(&from);
-                self.balances.insert(from, esbalance + balance);
+                // Escrow goes back even to a blacklisted buyer, where it stays frozen until seized.
+                self.not_blacklisted(&caller)?;
+                self.credit_account(from, esbalance);
+
+                self.escrow_balances.insert((from, to), 0);
+                self.escrow_fees.insert((from, to), 0);
+
+                Ok(())
+            } else {
+                Err(Error::NotPermission)
+            }
+        }
+
+        #[ink(message)]
+        pub fn escrow_balance(&self, from: AccountId, to: AccountId) -> Balance {
+            self.escrow_of_or_zero(&from, &to)
+        }
+
+        /// Move value from one account to another, returning the pending transfer id
+        /// when it is queued for review instead.
+        fn transfer_from_to(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<Option<u64>> {
+            self.not_blacklisted(&from)?;
+            self.not_blacklisted(&to)?;
+
+            self.accrue_credit(from);
+            let from_balance = self.balance_of_or_zero(&from);
+            let shortfall = value.saturating_sub(from_balance);
+            if shortfall > self.credit_available(from) {
+                return Err(Error::InsufficientBalance);
+            }
+            if shortfall > 0 {
+                self.within_reserve(shortfall)?;
+            }
+            self.spend_limit(from, value)?;
+
+            if shortfall > 0 {
+                let used = *self.credit_used.get(&from).unwrap_or(&0);
+                if used == 0 {
+                    self.credit_accrued_at.insert(from, self.env().block_timestamp());
+                }
+                self.credit_used.insert(from, used + shortfall);
+                *self.total_supply += shortfall;
+                self.env().emit_event(CreditDrawn {
+                    account: from,
+                    value: shortfall,
+                });
+            }
+
+            // Update the sender's balance.
+            self.balances.insert(from, from_balance + shortfall - value);
+
+            if value > *self.review_threshold {
+                return Ok(Some(self.queue_transfer(from, to, value, true)));
+            }
+
+            self.settle_transfer(from, to, value, true);
+            Ok(None)
+        }
+
+        /// Lock an already debited transfer for compliance review, returning its id.
+        fn queue_transfer(&mut self, from: AccountId, to: AccountId, value: Balance, charge_fee: bool) -> u64 {
+            let id = *self.next_pending_id;
+            *self.next_pending_id += 1;
+            self.pending_transfers.insert(id, PendingTransfer {
+                from,
+                to,
+                value,
+                requested_at: self.env().block_timestamp(),
+                spender: None,
+                subscription: None,
+                reference: None,
+                charge_fee,
+            });
+            self.env().emit_event(TransferQueued { id, from, to, value });
+            id
+        }
+
+        /// Pay out escrowed or held value without a transfer fee, queueing it for
+        /// review above the threshold like a transfer.
+        fn pay_out(&mut self, from: AccountId, to: AccountId, value: Balance) -> Option<u64> {
+            if value > *self.review_threshold {
+                return Some(self.queue_transfer(from, to, value, false));
+            }
+            self.settle_transfer(from, to, value, false);
+            None
+        }
+
+        /// Give a dropped pending transfer back to its sender, with its limit and allowance.
+        fn revert_pending(&mut self, pending: &PendingTransfer) {
+            self.credit_account(pending.from, pending.value);
+            self.release_limit(pending.from, pending.value);
+            if let Some(spender) = pending.spender {
+                let allowance = self.allowance_of_or_zero(&pending.from, &spender);
+                self.allowances
+                    .insert((pending.from, spender), allowance.saturating_add(pending.value));
+            }
+        }
+
+        fn count_collected(&mut self, id: u64, mut subscription: Subscription) {
+            let period = subscription.collected;
+            subscription.collected += 1;
+            subscription.pending = None;
+            self.subscriptions.insert(id, subscription);
+
+            self.env().emit_event(SubscriptionCollected {
+                id,
+                period,
+                amount: subscription.amount,
+            });
+        }
+
+        /// Credit a transfer already debited from the sender, taking the transfer fee
+        /// when `charge_fee` is set.
+        fn settle_transfer(&mut self, from: AccountId, to: AccountId, value: Balance, charge_fee: bool) {
+            let fee = if charge_fee { self.transfer_fee_of(from, to, value) } else { 0 };
+
+            // Update the receiver's balance.
+            self.credit_account(to, value - fee);
+
+            self.env().emit_event(Transfer {
+                from: Some(from),
+                to: Some(to),
+                value: value - fee,
+            });
+
+            if fee > 0 {
+                let treasury = *self.treasury;
+                self.credit_account(treasury, fee);
+
+                self.env().emit_event(Transfer {
+                    from: Some(from),
+                    to: Some(treasury),
+                    value: fee,
+                });
+                self.env().emit_event(FeeCharged { from, to, value, fee });
+            }
+        }
+
+        /// Add incoming funds to account, repaying its drawn credit first.
+        fn credit_account(&mut self, account: AccountId, value: Balance) {
+            self.accrue_credit(account);
+            let used = *self.credit_used.get(&account).unwrap_or(&0);
+            let repaid = core::cmp::min(used, value);
+            if repaid > 0 {
+                if repaid == used {
+                    // Settled lines leave the borrower set `set_credit_rate` walks.
+                    self.credit_used.take(&account);
+                    self.credit_accrued_at.take(&account);
+                } else {
+                    self.credit_used.insert(account, used - repaid);
+                }
+                *self.total_supply -= repaid;
+                self.env().emit_event(CreditRepaid {
+                    account,
+                    value: repaid,
+                });
+            }
+
+            let balance = self.balance_of_or_zero(&account);
+            self.balances.insert(account, balance + value - repaid);
+        }
+
+        fn pending_credit_interest(&self, account: &AccountId, used: Balance) -> Balance {
+            let interest = self.credit_interest_since_accrual(account, used);
+            if self.within_reserve(interest).is_err() {
+                return 0;
+            }
+            interest
+        }
+
+        fn credit_interest_since_accrual(&self, account: &AccountId, used: Balance) -> Balance {
+            let accrued_at = *self.credit_accrued_at.get(account).unwrap_or(&0);
+            let seconds = (self.env().block_timestamp().saturating_sub(accrued_at) / 1000) as Balance;
+            used.saturating_mul(*self.credit_rate).saturating_mul(seconds) / INTEREST_PRECISION
+        }
+
+        /// Add interest on drawn credit to the debt, minting it to the treasury.
+        /// Interest that would exceed the attested reserve stays deferred, and is
+        /// applied once the reserve covers it.
+        fn accrue_credit(&mut self, account: AccountId) {
+            let now = self.env().block_timestamp();
+            let used = *self.credit_used.get(&account).unwrap_or(&0);
+            if used == 0 {
+                return;
+            }
+
+            let interest = self.credit_interest_since_accrual(&account, used);
+            if interest == 0 {
+                return;
+            }
+            if self.within_reserve(interest).is_err() {
+                return;
+            }
+            let accrued_at = *self.credit_accrued_at.get(&account).unwrap_or(&0);
+            self.credit_accrued_at.insert(account, accrued_at + (now - accrued_at) / 1000 * 1000);
+            self.credit_used.insert(account, used + interest);
+
+            let treasury = *self.treasury;
+            *self.total_supply += interest;
+            self.credit_account(treasury, interest);
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(treasury),
+                value: interest,
+            });
+        }
+
+        fn review_pending_transfer(&self, id: u64) -> Result<PendingTransfer> {
+            self.only_compliance(self.env().caller())?;
+            self.pending_transfer(id).ok_or(Error::PendingTransferNotFound)
+        }
+
+        fn set_blacklisted(&mut self, account: AccountId, blacklisted: bool) -> Result<()> {
+            let officer = self.env().caller();
+            self.only_compliance(officer)?;
+            self.blacklist.insert(account, blacklisted);
+
+            self.env().emit_event(BlacklistUpdated {
+                account,
+                officer,
+                blacklisted,
+            });
+            Ok(())
+        }
+
+        fn spend_limit(&mut self, account: AccountId, value: Balance) -> Result<()> {
+            if self.limit_of(&account).is_none() {
+                return Ok(());
+            }
+            let (daily, monthly) = self.remaining_limit(account);
+            if value > daily || value > monthly {
+                return Err(Error::LimitExceeded);
+            }
+
+            let mut outflow = self.current_outflow(&account);
+            Self::record_outflow(&mut outflow, self.env().block_timestamp(), value);
+            self.outflows.insert(account, outflow);
+            Ok(())
+        }
+
+        /// Give back outflow charged for funds that returned to the account.
+        fn release_limit(&mut self, account: AccountId, value: Balance) {
+            if !self.outflows.contains_key(&account) {
+                return;
+            }
+            let mut outflow = self.current_outflow(&account);
+            Self::release_buckets(&mut outflow.hours, value);
+            Self::release_buckets(&mut outflow.days, value);
+            self.outflows.insert(account, outflow);
+        }
+
+        fn limit_of(&self, account: &AccountId) -> Option<LimitTier> {
+            if *account == *self.treasury || self.is_system_account(account) {
+                return None;
+            }
+            self.limit_tiers.get(&self.tier_of(*account)).copied()
+        }
+
+        fn current_outflow(&self, account: &AccountId) -> Outflow {
+            let outflow = self.outflows.get(account).cloned().unwrap_or_default();
+            Self::outflow_at(outflow, self.env().block_timestamp())
+        }
+
+        /// Drop the buckets that left the rolling windows at `now`.
+        fn outflow_at(mut outflow: Outflow, now: u64) -> Outflow {
+            let (hour, day) = (now / HOUR, now / DAY);
+            outflow.hours.retain(|(h, _)| hour.saturating_sub(*h) < HOURS_PER_DAY);
+            outflow.days.retain(|(d, _)| day.saturating_sub(*d) < DAYS_PER_MONTH);
+            outflow
+        }
+
+        fn record_outflow(outflow: &mut Outflow, now: u64, value: Balance) {
+            Self::add_to_bucket(&mut outflow.hours, now / HOUR, value);
+            Self::add_to_bucket(&mut outflow.days, now / DAY, value);
+        }
+
+        fn add_to_bucket(buckets: &mut Vec<(u64, Balance)>, key: u64, value: Balance) {
+            match buckets.last_mut() {
+                Some((last, spent)) if *last == key => *spent = spent.saturating_add(value),
+                _ => buckets.push((key, value)),
+            }
+        }
+
+        /// Take `value` off the newest buckets first.
+        fn release_buckets(buckets: &mut [(u64, Balance)], mut value: Balance) {
+            for (_, spent) in buckets.iter_mut().rev() {
+                let released = core::cmp::min(*spent, value);
+                *spent -= released;
+                value -= released;
+                if value == 0 {
+                    break;
+                }
+            }
+        }
+
+        /// Outflow within the rolling day and the rolling 30 days.
+        fn outflow_totals(outflow: &Outflow) -> (Balance, Balance) {
+            let sum = |buckets: &Vec<(u64, Balance)>| {
+                buckets.iter().fold(0, |total: Balance, (_, spent)| total.saturating_add(*spent))
+            };
+            (sum(&outflow.hours), sum(&outflow.days))
+        }
+
+        fn current_savings_index(&self) -> Balance {
+            self.accrued_savings().0
+        }
+
+        /// Savings index and interest to mint for the time since the last update.
+        /// Interest beyond the attested reserve stays owed to the pool and is
+        /// minted once the reserve covers it.
+        fn accrued_savings(&self) -> (Balance, Balance) {
+            let seconds = (self.env().block_timestamp() - *self.savings_updated_at) / 1000;
+            let index = *self.savings_index;
+            let growth = index
+                .saturating_mul(*self.savings_rate)
+                .saturating_mul(seconds as Balance)
+                / INTEREST_PRECISION;
+            let owed = self.total_savings_shares.saturating_mul(index + growth) / INTEREST_PRECISION;
+            let interest = owed.saturating_sub(*self.savings_pool);
+            (index + growth, core::cmp::min(interest, self.reserve_headroom()))
+        }
+
+        /// Tokens that can still be minted under the attested reserve.
+        fn reserve_headroom(&self) -> Balance {
+            if *self.reserve_enforced {
+                self.attested_reserve().saturating_sub(*self.total_supply)
+            } else {
+                Balance::MAX
+            }
+        }
+
+        /// Accrue interest since the last update and mint it into the savings pool.
+        fn drip(&mut self) {
+            let seconds = (self.env().block_timestamp() - *self.savings_updated_at) / 1000;
+            if seconds == 0 {
+                return;
+            }
+            let (index, interest) = self.accrued_savings();
+            *self.savings_index = index;
+            *self.savings_updated_at += seconds * 1000;
+            *self.savings_pool += interest;
+            *self.total_supply += interest;
+        }
+
+        fn pending_redemption(&self, id: u64) -> Result<Redemption> {
+            if !self.is_redemption_operator(self.env().caller()) {
+                return Err(Error::NotPermission);
+            }
+            let redemption = self.redemption(id).ok_or(Error::RedemptionNotFound)?;
+            if redemption.status != RedemptionStatus::Pending {
+                return Err(Error::RedemptionNotPending);
+            }
+            Ok(redemption)
+        }
 
-                self.escrow_balances.insert((from, to), 0);
+        fn settle_redemption(&mut self, id: u64, redemption: Redemption) {
+            self.redemptions.insert(id, redemption);
+            self.env().emit_event(RedemptionSettled {
+                id,
+                operator: self.env().caller(),
+                status: redemption.status,
+            });
+        }
+
+        fn attested_reserve(&self) -> Balance {
+            self.latest_attestation().map(|a| a.reserve).unwrap_or(0)
+        }
+
+        fn within_reserve(&self, value: Balance) -> Result<()> {
+            if *self.reserve_enforced
+                && self.total_supply.saturating_add(value) > self.attested_reserve()
+            {
+                Err(Error::ExceedsReserve)
+            } else {
+                Ok(())
+            }
+        }
 
+        fn only_compliance(&self, caller: AccountId) -> Result<()> {
+            if self.is_compliance_officer(caller) {
                 Ok(())
             } else {
                 Err(Error::NotPermission)
             }
         }
 
-        #[ink(message)]
-        pub fn escrow_balance(&self, from: AccountId, to: AccountId) -> Balance {
-            self.escrow_of_or_zero(&from, &to)
+        fn valid_reference(reference: &[u8]) -> Result<()> {
+            if reference.len() > MAX_REFERENCE_LEN {
+                Err(Error::ReferenceTooLong)
+            } else {
+                Ok(())
+            }
         }
 
-        fn transfer_from_to(
-            &mut self,
-            from: AccountId,
-            to: AccountId,
-            value: Balance,
-        ) -> Result<()> {
-            let from_balance = self.balance_of_or_zero(&from);
-            if from_balance < value {
-                return Err(Error::InsufficientBalance);
+        fn not_blacklisted(&self, account: &AccountId) -> Result<()> {
+            if self.is_blacklisted(*account) {
+                Err(Error::Blacklisted)
+            } else {
+                Ok(())
             }
+        }
 
-            // Update the sender's balance.
-            self.balances.insert(from, from_balance - value);
+        fn is_system_account(&self, account: &AccountId) -> bool {
+            *self.system_accounts.get(account).unwrap_or(&false)
+        }
 
-            // Update the receiver's balance.
-            let to_balance = self.balance_of_or_zero(&to);
-            self.balances.insert(to, to_balance + value);
+        fn is_fee_exempt(&self, from: &AccountId, to: &AccountId) -> bool {
+            *self.fee_exempt_senders.get(from).unwrap_or(&false)
+                || *self.fee_exempt_recipients.get(to).unwrap_or(&false)
+                || self.is_system_account(from)
+                || self.is_system_account(to)
+        }
 
-            self.env().emit_event(Transfer {
-                from: Some(from),
-                to: Some(to),
-                value,
+        fn emit_exemption(&self, account: AccountId) {
+            let (as_sender, as_recipient, system) = self.fee_exemption(account);
+            self.env().emit_event(FeeExemptionUpdated {
+                account,
+                as_sender,
+                as_recipient,
+                system,
             });
-            Ok(())
         }
 
         fn escrow_of_or_zero(&self, order: &AccountId, seller: &AccountId) -> Balance {
             *self.escrow_balances.get(&(*order, *seller)).unwrap_or(&0)
         }
 
-        fn cal_fee(&self, value: u64) -> u64 {
-            let mut fee = value.checked_div(100).unwrap_or(1);
-            if fee == 0 {
-                fee = 1;
+        fn update_vault(&mut self, owner: AccountId, vault: Vault) {
+            self.vaults.insert(owner, vault);
+            self.env().emit_event(VaultUpdated {
+                owner,
+                collateral: vault.collateral,
+                debt: vault.debt,
+            });
+        }
+
+        fn is_safe(&self, vault: &Vault) -> bool {
+            if vault.debt == 0 {
+                return true;
+            }
+            match self.price_of_collateral() {
+                Ok(price) => Self::is_safe_at(vault, price, *self.collateral_ratio),
+                Err(_) => false,
+            }
+        }
+
+        fn is_safe_at(vault: &Vault, price: Balance, ratio: u32) -> bool {
+            if vault.debt == 0 {
+                return true;
+            }
+            let value = vault.collateral.saturating_mul(price) / PRICE_PRECISION;
+            value.saturating_mul(100) >= vault.debt.saturating_mul(ratio as Balance)
+        }
+
+        /// Price of native SEL from the price oracle, or the fallback price while
+        /// no oracle is set. A missing or stale oracle price is not replaced by
+        /// the fallback.
+        fn price_of_collateral(&self) -> Result<Balance> {
+            let price = match *self.price_oracle {
+                Some(oracle) => {
+                    let oracle: PriceOracle = FromAccountId::from_account_id(oracle);
+                    oracle.latest_price(String::from(COLLATERAL_SYMBOL)).unwrap_or(0)
+                }
+                None => *self.collateral_price,
+            };
+            if price == 0 {
+                return Err(Error::PriceNotSet);
             }
-            fee
+            Ok(price)
+        }
+
+        fn escrow_fee_of_or_zero(&self, order: &AccountId, seller: &AccountId) -> Balance {
+            *self.escrow_fees.get(&(*order, *seller)).unwrap_or(&0)
+        }
+
+        fn cal_fee(&self, value: Balance) -> Balance {
+            let fee = value.saturating_mul(*self.fee_bps as Balance) / 10_000;
+            core::cmp::min(core::cmp::max(fee, *self.fee_min), *self.fee_max)
         }
 
         fn only_owner(&self, caller: AccountId) -> Result<()> {
@@ -290,6 +2573,32 @@ mod stable_currency {
 
         use ink_lang as ink;
 
+        fn set_caller(caller: AccountId) {
+            set_caller_with_value(caller, 0);
+        }
+
+        fn now() -> u64 {
+            ink_env::block_timestamp::<ink_env::DefaultEnvironment>().unwrap()
+        }
+
+        fn advance_seconds(seconds: u64) {
+            let until = now() + seconds * 1000;
+            while now() < until {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>().unwrap();
+            }
+        }
+
+        fn set_caller_with_value(caller: AccountId, value: Balance) {
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            let mut data =
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4]));
+            data.push_arg(&caller);
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller, callee, 1000000, value, data,
+            );
+        }
+
         #[ink::test]
         fn new_works() {
             let contract = StableCurrency::new(777, "rsel".to_string());
@@ -310,9 +2619,9 @@ mod stable_currency {
         fn transfer_works() {
             let mut contract = StableCurrency::new(100, "rsel".to_string());
             assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 100);
-            assert_eq!(contract.transfer(AccountId::from([0x0; 32]), 10), Ok(()));
+            assert_eq!(contract.transfer(AccountId::from([0x0; 32]), 10), Ok(None));
             assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 10);
-            assert_ne!(contract.transfer(AccountId::from([0x0; 32]), 100), Ok(()));
+            assert_ne!(contract.transfer(AccountId::from([0x0; 32]), 100), Ok(None));
         }
 
         #[ink::test]
@@ -326,6 +2635,711 @@ mod stable_currency {
             assert_eq!(contract.balance_of(AccountId::from([0x0; 32])), 10);
         }
 
+        #[ink::test]
+        fn transfer_fee_works() {
+            let mut contract = StableCurrency::new(10_000, "rsel".to_string());
+            let alice = AccountId::from([0x1; 32]);
+            let bob = AccountId::from([0x2; 32]);
+            let treasury = AccountId::from([0x3; 32]);
+            let system = AccountId::from([0x4; 32]);
+            assert_eq!(contract.set_transfer_fee(100, 15), Ok(()));
+            assert_eq!(contract.set_treasury(treasury), Ok(()));
+
+            assert_eq!(contract.transfer(bob, 1000), Ok(None));
+            assert_eq!(contract.balance_of(bob), 990);
+            assert_eq!(contract.balance_of(treasury), 10);
+
+            assert_eq!(contract.transfer(bob, 5000), Ok(None));
+            assert_eq!(contract.balance_of(bob), 5975);
+            assert_eq!(contract.balance_of(treasury), 25);
+            assert_eq!(contract.balance_of(alice), 4000);
+
+            assert_eq!(contract.set_fee_exemption(bob, false, true), Ok(()));
+            assert_eq!(contract.fee_exemption(bob), (false, true, false));
+            assert_eq!(contract.transfer(bob, 1000), Ok(None));
+            assert_eq!(contract.balance_of(bob), 6975);
+
+            assert_eq!(contract.set_system_account(system, true), Ok(()));
+            assert_eq!(contract.transfer(system, 1000), Ok(None));
+            assert_eq!(contract.balance_of(system), 1000);
+            assert_eq!(contract.balance_of(treasury), 25);
+        }
+
+        #[ink::test]
+        fn blacklist_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let owner = AccountId::from([0x1; 32]);
+            let officer = AccountId::from([0x2; 32]);
+            let sanctioned = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(sanctioned, 50), Ok(None));
+            assert_eq!(contract.blacklist(sanctioned), Err(Error::NotPermission));
+            assert_eq!(contract.set_compliance_officer(officer, true), Ok(()));
+
+            set_caller(officer);
+            assert_eq!(contract.blacklist(sanctioned), Ok(()));
+            assert_eq!(contract.is_blacklisted(sanctioned), true);
+
+            set_caller(owner);
+            assert_eq!(contract.transfer(sanctioned, 10), Err(Error::Blacklisted));
+            assert_eq!(
+                contract.create_payment(sanctioned, 10, Vec::new()),
+                Err(Error::Blacklisted)
+            );
+            set_caller(sanctioned);
+            assert_eq!(contract.transfer(owner, 10), Err(Error::Blacklisted));
+            assert_eq!(contract.approve(owner, 10), false);
+            assert_eq!(contract.mint_from_vault(10), Err(Error::Blacklisted));
+            assert_eq!(contract.repay(10), Err(Error::Blacklisted));
+            assert_eq!(contract.withdraw_collateral(10), Err(Error::Blacklisted));
+            assert_eq!(contract.deposit_savings(10), Err(Error::Blacklisted));
+
+            set_caller(officer);
+            assert_eq!(contract.unblacklist(sanctioned), Ok(()));
+            set_caller(sanctioned);
+            assert_eq!(contract.transfer(owner, 10), Ok(None));
+        }
+
+        #[ink::test]
+        fn seize_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let officer = AccountId::from([0x2; 32]);
+            let holder = AccountId::from([0x3; 32]);
+            let custody = AccountId::from([0x4; 32]);
+            let reference = Hash::from([0x9; 32]);
+            assert_eq!(contract.transfer(holder, 60), Ok(None));
+            assert_eq!(
+                contract.seize(holder, 10, custody, reference),
+                Err(Error::NotPermission)
+            );
+            assert_eq!(contract.set_compliance_officer(officer, true), Ok(()));
+
+            set_caller(officer);
+            assert_eq!(contract.blacklist(holder), Ok(()));
+            assert_eq!(
+                contract.seize(holder, 61, custody, reference),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(contract.seize(holder, 60, custody, reference), Ok(()));
+            assert_eq!(contract.balance_of(holder), 0);
+            assert_eq!(contract.balance_of(custody), 60);
+        }
+
+        #[ink::test]
+        fn attest_reserve_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let auditor = AccountId::from([0x2; 32]);
+            let doc_hash = Hash::from([0x7; 32]);
+            assert_eq!(contract.reserve_coverage(), (0, 100, false));
+            assert_eq!(
+                contract.attest_reserve(150, 0, "ipfs://report".to_string(), doc_hash),
+                Err(Error::NotPermission)
+            );
+            assert_eq!(contract.set_auditor(auditor, true), Ok(()));
+
+            set_caller(auditor);
+            assert_eq!(
+                contract.attest_reserve(150, 0, "ipfs://report".to_string(), doc_hash),
+                Ok(())
+            );
+            let latest = contract.latest_attestation().unwrap();
+            assert_eq!(latest.reserve, 150);
+            assert_eq!(latest.auditor, auditor);
+            assert_eq!(latest.document.doc_hash, doc_hash);
+            assert_eq!(contract.attestation_count(), 1);
+            assert_eq!(contract.attestation(0), Some(latest));
+            assert_eq!(contract.reserve_coverage(), (150, 100, true));
+        }
+
+        #[ink::test]
+        fn reserve_enforced_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let owner = AccountId::from([0x1; 32]);
+            let auditor = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_auditor(auditor, true), Ok(()));
+            assert_eq!(contract.set_reserve_enforced(true), Ok(()));
+            assert_eq!(contract.inc_supply(1), Err(Error::ExceedsReserve));
+
+            set_caller(auditor);
+            assert_eq!(
+                contract.attest_reserve(150, 0, "ipfs://report".to_string(), Hash::from([0x7; 32])),
+                Ok(())
+            );
+            set_caller(owner);
+            assert_eq!(contract.inc_supply(51), Err(Error::ExceedsReserve));
+            assert_eq!(contract.inc_supply(50), Ok(()));
+            assert_eq!(contract.total_supply(), 150);
+
+            assert_eq!(contract.set_collateral_price(PRICE_PRECISION), Ok(()));
+            set_caller_with_value(owner, 300);
+            assert_eq!(contract.open_vault(), Ok(()));
+            assert_eq!(contract.mint_from_vault(1), Err(Error::ExceedsReserve));
+            assert_eq!(contract.vault_of(owner).debt, 0);
+        }
+
+        #[ink::test]
+        fn redemption_paid_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let holder = AccountId::from([0x1; 32]);
+            let operator = AccountId::from([0x2; 32]);
+            let bank_reference = Hash::from([0x8; 32]);
+            assert_eq!(contract.request_redemption(101, bank_reference), Err(Error::InsufficientBalance));
+            assert_eq!(contract.request_redemption(0, bank_reference), Err(Error::InvalidAmount));
+            assert_eq!(contract.request_redemption(40, bank_reference), Ok(0));
+            assert_eq!(contract.balance_of(holder), 60);
+            assert_eq!(contract.redemptions_of(holder), vec![0]);
+            assert_eq!(contract.mark_redemption_paid(0), Err(Error::NotPermission));
+            assert_eq!(contract.set_redemption_operator(operator, true), Ok(()));
+
+            set_caller(operator);
+            assert_eq!(contract.mark_redemption_paid(1), Err(Error::RedemptionNotFound));
+            assert_eq!(contract.mark_redemption_paid(0), Ok(()));
+            assert_eq!(contract.total_supply(), 60);
+            assert_eq!(contract.redemption(0).unwrap().status, RedemptionStatus::Paid);
+            assert_eq!(contract.reject_redemption(0), Err(Error::RedemptionNotPending));
+        }
+
+        #[ink::test]
+        fn redemption_rejected_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let holder = AccountId::from([0x1; 32]);
+            let operator = AccountId::from([0x2; 32]);
+            let bank_reference = Hash::from([0x8; 32]);
+            assert_eq!(contract.set_redemption_operator(operator, true), Ok(()));
+            assert_eq!(contract.request_redemption(40, bank_reference), Ok(0));
+            assert_eq!(contract.request_redemption(10, bank_reference), Ok(1));
+
+            set_caller(operator);
+            assert_eq!(contract.reject_redemption(0), Ok(()));
+            assert_eq!(contract.balance_of(holder), 90);
+            assert_eq!(contract.total_supply(), 100);
+            assert_eq!(contract.redemptions_by_status(RedemptionStatus::Pending, 0, 10), vec![1]);
+            assert_eq!(contract.redemptions_by_status(RedemptionStatus::Rejected, 0, 10), vec![0]);
+            assert_eq!(contract.redemptions_by_status(RedemptionStatus::Pending, 0, 1), Vec::<u64>::new());
+            assert_eq!(contract.redemptions_by_status(RedemptionStatus::Pending, 1, 1), vec![1]);
+        }
+
+        #[ink::test]
+        fn limit_tier_works() {
+            let mut contract = StableCurrency::new(10_000, "rsel".to_string());
+            let owner = AccountId::from([0x1; 32]);
+            let unverified = AccountId::from([0x2; 32]);
+            let verified = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(unverified, 1000), Ok(None));
+            assert_eq!(contract.transfer(verified, 1000), Ok(None));
+            assert_eq!(contract.set_limit_tier(0, 100, 300), Ok(()));
+            assert_eq!(contract.assign_tier(verified, 1), Ok(()));
+            assert_eq!(contract.assign_tier(owner, 1), Ok(()));
+            assert_eq!(contract.remaining_limit(unverified), (100, 300));
+            assert_eq!(contract.remaining_limit(verified), (Balance::MAX, Balance::MAX));
+
+            set_caller(unverified);
+            assert_eq!(contract.transfer(owner, 60), Ok(None));
+            assert_eq!(contract.remaining_limit(unverified), (40, 240));
+            assert_eq!(contract.transfer(owner, 41), Err(Error::LimitExceeded));
+            assert_eq!(contract.create_payment(owner, 39, Vec::new()), Ok(()));
+            assert_eq!(contract.transfer(owner, 1), Err(Error::LimitExceeded));
+            assert_eq!(contract.assign_tier(unverified, 1), Err(Error::NotPermission));
+
+            set_caller(verified);
+            assert_eq!(contract.transfer(owner, 1000), Ok(None));
+
+            set_caller(owner);
+            assert_eq!(contract.set_system_account(unverified, true), Ok(()));
+            assert_eq!(contract.remaining_limit(unverified), (Balance::MAX, Balance::MAX));
+            set_caller(unverified);
+            assert_eq!(contract.transfer(owner, 500), Ok(None));
+        }
+
+        #[ink::test]
+        fn outflow_window_rolls() {
+            let start = 10 * DAY + 23 * HOUR;
+            let mut outflow = Outflow::default();
+            StableCurrency::record_outflow(&mut outflow, start, 60);
+            StableCurrency::record_outflow(&mut outflow, start + 2 * HOUR, 40);
+
+            // Spending across midnight still counts both payments in the rolling day.
+            let at = |now| StableCurrency::outflow_totals(&StableCurrency::outflow_at(outflow.clone(), now));
+            assert_eq!(at(start + 2 * HOUR), (100, 100));
+            assert_eq!(at(start + DAY), (40, 100));
+            assert_eq!(at(start + 2 * HOUR + DAY), (0, 100));
+            assert_eq!(at(start + 30 * DAY), (0, 40));
+            assert_eq!(at(start + 31 * DAY), (0, 0));
+
+            StableCurrency::release_buckets(&mut outflow.hours, 50);
+            assert_eq!(StableCurrency::outflow_totals(&outflow).0, 50);
+        }
+
+        #[ink::test]
+        fn authorize_capture_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let payer = AccountId::from([0x1; 32]);
+            let merchant = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_treasury(AccountId::from([0x9; 32])), Ok(()));
+            assert_eq!(contract.set_limit_tier(0, 100, 100), Ok(()));
+            assert_eq!(contract.authorize(merchant, 101, 60_000), Err(Error::InsufficientBalance));
+            assert_eq!(contract.authorize(merchant, 50, 60_000), Ok(0));
+            assert_eq!(contract.balance_of(payer), 50);
+            assert_eq!(contract.remaining_limit(payer), (50, 50));
+            assert_eq!(contract.capture(0, 30), Err(Error::NotPermission));
+            assert_eq!(contract.void(0), Err(Error::NotPermission));
+
+            set_caller(merchant);
+            assert_eq!(contract.capture(0, 51), Err(Error::CaptureExceedsHold));
+            assert_eq!(contract.capture(0, 30), Ok(None));
+            assert_eq!(contract.balance_of(merchant), 30);
+            assert_eq!(contract.balance_of(payer), 70);
+            assert_eq!(contract.remaining_limit(payer), (70, 70));
+            assert_eq!(contract.hold(0), None);
+            assert_eq!(contract.capture(0, 1), Err(Error::HoldNotFound));
+        }
+
+        #[ink::test]
+        fn void_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let payer = AccountId::from([0x1; 32]);
+            let merchant = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_treasury(AccountId::from([0x9; 32])), Ok(()));
+            assert_eq!(contract.set_limit_tier(0, 100, 100), Ok(()));
+            assert_eq!(contract.authorize(merchant, 50, 60_000), Ok(0));
+
+            set_caller(merchant);
+            assert_eq!(contract.void(0), Ok(()));
+            assert_eq!(contract.balance_of(payer), 100);
+            assert_eq!(contract.remaining_limit(payer), (100, 100));
+            assert_eq!(contract.balance_of(merchant), 0);
+            assert_eq!(contract.void(0), Err(Error::HoldNotFound));
+        }
+
+        #[ink::test]
+        fn blacklisted_funds_held_are_released() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let payer = AccountId::from([0x1; 32]);
+            let merchant = AccountId::from([0x2; 32]);
+            let officer = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_compliance_officer(officer, true), Ok(()));
+            assert_eq!(contract.authorize(merchant, 50, 60_000), Ok(0));
+            assert_eq!(contract.create_payment(merchant, 20, Vec::new()), Ok(()));
+
+            set_caller(officer);
+            assert_eq!(contract.blacklist(payer), Ok(()));
+            assert_eq!(contract.void(0), Ok(()));
+
+            set_caller(merchant);
+            assert_eq!(contract.refund(payer, merchant), Ok(()));
+            assert_eq!(contract.balance_of(payer), 100);
+            assert_eq!(contract.escrow_balance(payer, merchant), 0);
+
+            set_caller(officer);
+            assert_eq!(contract.seize(payer, 100, officer, Hash::from([0x7; 32])), Ok(()));
+            assert_eq!(contract.balance_of(officer), 100);
+        }
+
+        #[ink::test]
+        fn subscription_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let subscriber = AccountId::from([0x1; 32]);
+            let merchant = AccountId::from([0x2; 32]);
+            assert_eq!(contract.subscribe(merchant, 10, 0, None), Err(Error::InvalidPeriod));
+            assert_eq!(contract.subscribe(merchant, 10, 1, None), Ok(0));
+            assert_eq!(contract.collect(0), Err(Error::NotPermission));
+
+            set_caller(merchant);
+            assert_eq!(contract.collect(0), Ok(()));
+            assert_eq!(contract.collect(0), Err(Error::PaymentNotDue));
+            assert_eq!(contract.balance_of(merchant), 10);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().unwrap();
+            assert_eq!(contract.collect(0), Ok(()));
+            assert_eq!(contract.balance_of(merchant), 20);
+            assert_eq!(contract.balance_of(subscriber), 80);
+
+            // Collections never draw on the subscriber's credit line.
+            set_caller(subscriber);
+            assert_eq!(contract.set_credit_limit(subscriber, 100), Ok(()));
+            assert_eq!(contract.subscribe(merchant, 90, 1, None), Ok(1));
+            set_caller(merchant);
+            assert_eq!(contract.collect(1), Err(Error::InsufficientBalance));
+            assert_eq!(contract.credit_used(subscriber), 0);
+
+            set_caller(subscriber);
+            assert_eq!(contract.cancel_subscription(0), Ok(()));
+            set_caller(merchant);
+            assert_eq!(contract.collect(0), Err(Error::SubscriptionNotFound));
+        }
+
+        #[ink::test]
+        fn missed_periods_lapse() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let subscriber = AccountId::from([0x1; 32]);
+            let merchant = AccountId::from([0x2; 32]);
+            let start = now();
+            assert_eq!(contract.subscribe(merchant, 10, 1000, None), Ok(0));
+
+            advance_seconds(3);
+            let current = (now() - start) / 1000;
+            set_caller(merchant);
+            assert_eq!(contract.collect(0), Ok(()));
+            assert_eq!(contract.collect(0), Err(Error::PaymentNotDue));
+            assert_eq!(contract.balance_of(merchant), 10);
+            assert_eq!(contract.balance_of(subscriber), 90);
+            assert_eq!(contract.subscription(0).unwrap().collected, current + 1);
+        }
+
+        #[ink::test]
+        fn cancel_voids_pending_collection() {
+            let mut contract = StableCurrency::new(10_000, "rsel".to_string());
+            let subscriber = AccountId::from([0x1; 32]);
+            let officer = AccountId::from([0x2; 32]);
+            let merchant = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_compliance_officer(officer, true), Ok(()));
+            assert_eq!(contract.set_review_threshold(1000), Ok(()));
+            assert_eq!(contract.subscribe(merchant, 2000, 60_000, None), Ok(0));
+
+            set_caller(merchant);
+            assert_eq!(contract.collect(0), Ok(()));
+            assert_eq!(contract.balance_of(subscriber), 8000);
+
+            set_caller(subscriber);
+            assert_eq!(contract.cancel_subscription(0), Ok(()));
+            assert_eq!(contract.balance_of(subscriber), 10_000);
+            assert_eq!(contract.pending_transfer_ids(), Vec::<u64>::new());
+
+            set_caller(officer);
+            assert_eq!(contract.approve_transfer(0), Err(Error::PendingTransferNotFound));
+            assert_eq!(contract.balance_of(merchant), 0);
+        }
+
+        #[ink::test]
+        fn transfer_with_reference_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let merchant = AccountId::from([0x2; 32]);
+            assert_eq!(
+                contract.transfer_with_reference(merchant, 40, b"INV-001".to_vec()),
+                Ok(None)
+            );
+            assert_eq!(contract.balance_of(merchant), 40);
+            assert_eq!(
+                contract.transfer_with_reference(merchant, 61, b"INV-002".to_vec()),
+                Err(Error::InsufficientBalance)
+            );
+
+            let emitted = ink_env::test::recorded_events().count();
+            assert_eq!(contract.create_payment(merchant, 30, b"INV-003".to_vec()), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), emitted + 1);
+
+            let long = vec![0x1; MAX_REFERENCE_LEN + 1];
+            assert_eq!(
+                contract.transfer_with_reference(merchant, 1, long.clone()),
+                Err(Error::ReferenceTooLong)
+            );
+            assert_eq!(contract.create_payment(merchant, 1, long), Err(Error::ReferenceTooLong));
+        }
+
+        #[ink::test]
+        fn savings_works() {
+            let mut contract = StableCurrency::new(100, "rsel".to_string());
+            let account = AccountId::from([0x1; 32]);
+            assert_eq!(contract.set_savings_rate(INTEREST_PRECISION / 10), Ok(()));
+            assert_eq!(contract.deposit_savings(101), Err(Error::InsufficientBalance));
+            assert_eq!(contract.deposit_savings(0), Err(Error::InvalidAmount));
+            assert_eq!(contract.withdraw_savings(Balance::MAX), Err(Error::InsufficientBalance));
+            assert_eq!(contract.deposit_savings(60), Ok(()));
+            assert_eq!(contract.balance_of(account), 40);
+            assert_eq!(contract.savings_pool(), 60);
+            assert_eq!(contract.savings_balance(account), 60);
+            assert_eq!(contract.total_supply(), 100);
+
+            advance_seconds(1);
+            // 10% a second on 60 for every whole second elapsed.
+            let seconds = now() / 1000;
+            let savings = contract.savings_balance(account);
+            assert_eq!(savings, 60 + 6 * seconds as Balance);
+            assert!(savings > 60);
+            assert_eq!(contract.withdraw_savings(savings + 1), Err(Error::InsufficientBalance));
+            assert_eq!(contract.withdraw_savings(savings), Ok(()));
+            assert_eq!(contract.balance_of(account), 40 + savings);
+            assert_eq!(contract.total_supply(), contract.balance_of(account) + contract.savings_pool());
+
+            // Interest past the attested reserve is deferred, not dropped.
+            let supply = contract.total_supply();
+            assert_eq!(contract.set_reserve_enforced(true), Ok(()));
+            assert_eq!(contract.set_auditor(account, true), Ok(()));
+            assert_eq!(
+                contract.attest_reserve(supply, now(), "ipfs://report".to_string(), Hash::from([0x7; 32])),
+                Ok(())
+            );
+            assert_eq!(contract.deposit_savings(40), Ok(()));
+            let saved = contract.savings_balance(account);
+            advance_seconds(1);
+            let owed = contract.savings_balance(account);
+            assert!(owed > saved);
+            assert_eq!(contract.withdraw_savings(owed), Err(Error::InsufficientBalance));
+            assert_eq!(contract.total_supply(), supply);
+
+            assert_eq!(
+                contract.attest_reserve(supply * 2, now(), "ipfs://report".to_string(), Hash::from([0x7; 32])),
+                Ok(())
+            );
+            advance_seconds(1);
+            let owed = contract.savings_balance(account);
+            assert_eq!(contract.withdraw_savings(owed), Ok(()));
+            assert!(contract.total_supply() > supply);
+            assert_eq!(contract.total_supply(), contract.balance_of(account) + contract.savings_pool());
+        }
+
+        #[ink::test]
+        fn review_queue_works() {
+            let mut contract = StableCurrency::new(10_000, "rsel".to_string());
+            let sender = AccountId::from([0x1; 32]);
+            let officer = AccountId::from([0x2; 32]);
+            let receiver = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_compliance_officer(officer, true), Ok(()));
+            assert_eq!(contract.set_review_threshold(1000), Ok(()));
+
+            assert_eq!(contract.transfer(receiver, 1000), Ok(None));
+            assert_eq!(contract.balance_of(receiver), 1000);
+            assert_eq!(contract.transfer(receiver, 3000), Ok(Some(0)));
+            assert_eq!(contract.transfer(receiver, 2000), Ok(Some(1)));
+            assert_eq!(contract.balance_of(sender), 4000);
+            assert_eq!(contract.balance_of(receiver), 1000);
+            assert_eq!(contract.pending_transfer_ids(), vec![0, 1]);
+            assert_eq!(contract.approve_transfer(0), Err(Error::NotPermission));
+
+            set_caller(officer);
+            assert_eq!(contract.approve_transfer(0), Ok(()));
+            assert_eq!(contract.balance_of(receiver), 4000);
+            assert_eq!(contract.reject_transfer(1), Ok(()));
+            assert_eq!(contract.balance_of(sender), 6000);
+            assert_eq!(contract.pending_transfer_ids(), Vec::<u64>::new());
+            assert_eq!(contract.reject_transfer(1), Err(Error::PendingTransferNotFound));
+        }
+
+        #[ink::test]
+        fn rejected_transfer_restores_allowance_and_limit() {
+            let mut contract = StableCurrency::new(10_000, "rsel".to_string());
+            let sender = AccountId::from([0x1; 32]);
+            let spender = AccountId::from([0x2; 32]);
+            let receiver = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_treasury(AccountId::from([0x9; 32])), Ok(()));
+            assert_eq!(contract.set_compliance_officer(spender, true), Ok(()));
+            assert_eq!(contract.set_review_threshold(1000), Ok(()));
+            assert_eq!(contract.set_limit_tier(0, 5000, 5000), Ok(()));
+            assert_eq!(contract.approve(spender, 3000), true);
+
+            set_caller(spender);
+            assert_eq!(contract.transfer_from(sender, receiver, 2000), Ok(Some(0)));
+            assert_eq!(contract.allowance(sender, spender), 1000);
+            assert_eq!(contract.remaining_limit(sender), (3000, 3000));
+            assert_eq!(contract.pending_transfer(0).unwrap().spender, Some(spender));
+
+            assert_eq!(contract.reject_transfer(0), Ok(()));
+            assert_eq!(contract.balance_of(sender), 10_000);
+            assert_eq!(contract.allowance(sender, spender), 3000);
+            assert_eq!(contract.remaining_limit(sender), (5000, 5000));
+        }
+
+        #[ink::test]
+        fn queued_payment_and_collection_wait_for_approval() {
+            let mut contract = StableCurrency::new(10_000, "rsel".to_string());
+            let officer = AccountId::from([0x2; 32]);
+            let merchant = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_compliance_officer(officer, true), Ok(()));
+            assert_eq!(contract.set_review_threshold(1000), Ok(()));
+
+            let emitted = ink_env::test::recorded_events().count();
+            assert_eq!(contract.transfer_with_reference(merchant, 2000, b"INV-001".to_vec()), Ok(Some(0)));
+            // Only `TransferQueued`, the `Payment` waits for approval.
+            assert_eq!(ink_env::test::recorded_events().count(), emitted + 1);
+            assert_eq!(contract.pending_transfer(0).unwrap().reference, Some(b"INV-001".to_vec()));
+
+            assert_eq!(contract.subscribe(merchant, 2000, 60_000, None), Ok(0));
+            set_caller(merchant);
+            assert_eq!(contract.collect(0), Ok(()));
+            assert_eq!(contract.subscription(0).unwrap().collected, 0);
+            assert_eq!(contract.subscription(0).unwrap().pending, Some(1));
+            assert_eq!(contract.collect(0), Err(Error::CollectionPending));
+
+            set_caller(officer);
+            assert_eq!(contract.approve_transfer(0), Ok(()));
+            assert_eq!(contract.reject_transfer(1), Ok(()));
+            assert_eq!(contract.balance_of(merchant), 2000);
+            assert_eq!(contract.subscription(0).unwrap().collected, 0);
+            assert_eq!(contract.subscription(0).unwrap().pending, None);
+
+            set_caller(merchant);
+            assert_eq!(contract.collect(0), Ok(()));
+            set_caller(officer);
+            assert_eq!(contract.approve_transfer(2), Ok(()));
+            assert_eq!(contract.subscription(0).unwrap().collected, 1);
+            assert_eq!(contract.balance_of(merchant), 4000);
+        }
+
+        #[ink::test]
+        fn escrow_and_hold_payouts_wait_for_review() {
+            let mut contract = StableCurrency::new(10_000, "rsel".to_string());
+            let payer = AccountId::from([0x1; 32]);
+            let officer = AccountId::from([0x2; 32]);
+            let seller = AccountId::from([0x3; 32]);
+            let merchant = AccountId::from([0x4; 32]);
+            let treasury = AccountId::from([0x9; 32]);
+            assert_eq!(contract.set_treasury(treasury), Ok(()));
+            assert_eq!(contract.set_transfer_fee(100, 15), Ok(()));
+            assert_eq!(contract.set_compliance_officer(officer, true), Ok(()));
+            assert_eq!(contract.set_review_threshold(1000), Ok(()));
+
+            assert_eq!(contract.create_payment(seller, 2000, Vec::new()), Ok(()));
+            assert_eq!(contract.complete_payment(payer, seller), Ok(Some(0)));
+            assert_eq!(contract.balance_of(seller), 0);
+            assert_eq!(contract.balance_of(treasury), 20);
+
+            assert_eq!(contract.authorize(merchant, 2000, 60_000), Ok(0));
+            set_caller(merchant);
+            assert_eq!(contract.capture(0, 1500), Ok(Some(1)));
+            assert_eq!(contract.balance_of(merchant), 0);
+            assert_eq!(contract.balance_of(payer), 6480);
+
+            set_caller(officer);
+            assert_eq!(contract.approve_transfer(0), Ok(()));
+            // Escrow and hold payouts don't take the transfer fee.
+            assert_eq!(contract.balance_of(seller), 2000);
+            assert_eq!(contract.reject_transfer(1), Ok(()));
+            assert_eq!(contract.balance_of(merchant), 0);
+            assert_eq!(contract.balance_of(payer), 7980);
+            assert_eq!(contract.balance_of(treasury), 20);
+        }
+
+        #[ink::test]
+        fn credit_line_works() {
+            let mut contract = StableCurrency::new(1000, "rsel".to_string());
+            let owner = AccountId::from([0x1; 32]);
+            let merchant = AccountId::from([0x2; 32]);
+            let supplier = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(merchant, 20), Ok(None));
+            assert_eq!(contract.set_credit_limit(merchant, 100), Ok(()));
+            assert_eq!(contract.credit_available(merchant), 100);
+
+            set_caller(merchant);
+            assert_eq!(contract.transfer(supplier, 121), Err(Error::InsufficientBalance));
+            assert_eq!(contract.transfer(supplier, 80), Ok(None));
+            assert_eq!(contract.balance_of(merchant), 0);
+            assert_eq!(contract.balance_of(supplier), 80);
+            assert_eq!(contract.credit_used(merchant), 60);
+            assert_eq!(contract.credit_available(merchant), 40);
+            assert_eq!(contract.total_supply(), 1060);
+
+            set_caller(owner);
+            assert_eq!(contract.transfer(merchant, 100), Ok(None));
+            assert_eq!(contract.credit_used(merchant), 0);
+            assert_eq!(contract.balance_of(merchant), 40);
+            assert_eq!(contract.total_supply(), 1000);
+            assert!(!contract.credit_used.contains_key(&merchant));
+        }
+
+        #[ink::test]
+        fn inflows_repay_credit() {
+            let mut contract = StableCurrency::new(1000, "rsel".to_string());
+            let merchant = AccountId::from([0x2; 32]);
+            let supplier = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(merchant, 100), Ok(None));
+            assert_eq!(contract.set_credit_limit(merchant, 100), Ok(()));
+
+            set_caller(merchant);
+            assert_eq!(contract.authorize(supplier, 100, 60_000), Ok(0));
+            assert_eq!(contract.transfer(supplier, 60), Ok(None));
+            assert_eq!(contract.credit_used(merchant), 60);
+            assert_eq!(contract.total_supply(), 1060);
+
+            set_caller(supplier);
+            assert_eq!(contract.void(0), Ok(()));
+            assert_eq!(contract.credit_used(merchant), 0);
+            assert_eq!(contract.balance_of(merchant), 40);
+            assert_eq!(contract.total_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn credit_interest_waits_for_reserve() {
+            let mut contract = StableCurrency::new(1000, "rsel".to_string());
+            let owner = AccountId::from([0x1; 32]);
+            let auditor = AccountId::from([0x2; 32]);
+            let merchant = AccountId::from([0x3; 32]);
+            let supplier = AccountId::from([0x4; 32]);
+            assert_eq!(contract.transfer(merchant, 20), Ok(None));
+            assert_eq!(contract.set_credit_limit(merchant, 100), Ok(()));
+            set_caller(merchant);
+            assert_eq!(contract.transfer(supplier, 40), Ok(None));
+
+            set_caller(owner);
+            assert_eq!(contract.set_auditor(auditor, true), Ok(()));
+            assert_eq!(contract.set_reserve_enforced(true), Ok(()));
+            set_caller(auditor);
+            assert_eq!(
+                contract.attest_reserve(1020, 0, "ipfs://report".to_string(), Hash::from([0x7; 32])),
+                Ok(())
+            );
+
+            set_caller(owner);
+            let start = now();
+            assert_eq!(contract.set_credit_rate(INTEREST_PRECISION / 10), Ok(()));
+            advance_seconds(2);
+            let seconds = (now() - start) / 1000;
+            // Accruing while the reserve is exhausted defers the interest.
+            let emitted = ink_env::test::recorded_events().count();
+            assert_eq!(contract.set_credit_rate(INTEREST_PRECISION / 10), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), emitted + 1);
+            assert_eq!(contract.credit_used(merchant), 20);
+
+            set_caller(auditor);
+            assert_eq!(
+                contract.attest_reserve(2000, 0, "ipfs://report".to_string(), Hash::from([0x7; 32])),
+                Ok(())
+            );
+            assert_eq!(contract.credit_used(merchant), 20 + 2 * seconds as Balance);
+        }
+
+        #[ink::test]
+        fn credit_line_review_and_reserve_works() {
+            let mut contract = StableCurrency::new(1000, "rsel".to_string());
+            let owner = AccountId::from([0x1; 32]);
+            let merchant = AccountId::from([0x2; 32]);
+            let supplier = AccountId::from([0x3; 32]);
+            assert_eq!(contract.transfer(merchant, 20), Ok(None));
+            assert_eq!(contract.set_credit_limit(merchant, 100), Ok(()));
+            assert_eq!(contract.set_compliance_officer(owner, true), Ok(()));
+            assert_eq!(contract.set_review_threshold(50), Ok(()));
+
+            set_caller(merchant);
+            assert_eq!(contract.transfer(supplier, 80), Ok(Some(0)));
+            assert_eq!(contract.credit_used(merchant), 60);
+            assert_eq!(contract.total_supply(), 1060);
+
+            set_caller(owner);
+            assert_eq!(contract.reject_transfer(0), Ok(()));
+            assert_eq!(contract.credit_used(merchant), 0);
+            assert_eq!(contract.balance_of(merchant), 20);
+            assert_eq!(contract.total_supply(), 1000);
+
+            assert_eq!(contract.set_reserve_enforced(true), Ok(()));
+            set_caller(merchant);
+            assert_eq!(contract.transfer(supplier, 30), Err(Error::ExceedsReserve));
+            assert_eq!(contract.balance_of(merchant), 20);
+
+            set_caller(owner);
+            assert_eq!(contract.set_reserve_enforced(false), Ok(()));
+            set_caller(merchant);
+            assert_eq!(contract.transfer(supplier, 40), Ok(None));
+            assert_eq!(contract.credit_used(merchant), 20);
+
+            // 10% a second accrues until the rate changes, none after it is set to zero.
+            set_caller(owner);
+            let start = now();
+            assert_eq!(contract.set_credit_rate(INTEREST_PRECISION / 10), Ok(()));
+            advance_seconds(1);
+            let seconds = (now() - start) / 1000;
+            assert_eq!(contract.set_credit_rate(0), Ok(()));
+            advance_seconds(1);
+            assert_eq!(contract.credit_used(merchant), 20 + 2 * seconds as Balance);
+        }
+
         #[ink::test]
         fn onlyowner_works() {
             let contract = StableCurrency::new(777, "rsel".to_string());
@@ -358,13 +3372,116 @@ mod stable_currency {
             assert_eq!(contract.balance_of(AccountId::from([0x1; 32])), 767);
         }
 
+        #[ink::test]
+        fn mint_works() {
+            let mut contract = StableCurrency::new(777, "rsel".to_string());
+            let minter = AccountId::from([0x2; 32]);
+            let holder = AccountId::from([0x3; 32]);
+            assert_eq!(contract.set_minter(minter, 500), Ok(()));
+            assert_eq!(contract.is_minter(minter), true);
+
+            set_caller(minter);
+            assert_eq!(contract.mint(holder, 300), Ok(()));
+            assert_eq!(contract.balance_of(holder), 300);
+            assert_eq!(contract.total_supply(), 1077);
+            assert_eq!(contract.minter_allowance(minter), 200);
+            assert_eq!(contract.mint(holder, 201), Err(Error::MintAllowanceExceeded));
+            assert_eq!(contract.set_minter(minter, 1000), Err(Error::OnlyOwner));
+
+            set_caller(holder);
+            assert_eq!(contract.mint(holder, 1), Err(Error::NotPermission));
+        }
+
+        #[ink::test]
+        fn burn_works() {
+            let mut contract = StableCurrency::new(777, "rsel".to_string());
+            let minter = AccountId::from([0x2; 32]);
+            assert_eq!(contract.set_minter(minter, 500), Ok(()));
+            set_caller(minter);
+            assert_eq!(contract.mint(minter, 100), Ok(()));
+            assert_eq!(contract.burn(40), Ok(()));
+            assert_eq!(contract.balance_of(minter), 60);
+            assert_eq!(contract.total_supply(), 837);
+            assert_eq!(contract.burn(61), Err(Error::InsufficientBalance));
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.remove_minter(minter), Ok(()));
+            set_caller(minter);
+            assert_eq!(contract.burn(10), Err(Error::NotPermission));
+        }
+
+        #[ink::test]
+        fn vault_mint_and_repay_works() {
+            let mut contract = StableCurrency::new(0, "rsel".to_string());
+            let user = AccountId::from([0x2; 32]);
+            set_caller_with_value(user, 300);
+            assert_eq!(contract.open_vault(), Ok(()));
+            assert_eq!(contract.vault_of(user), Vault { collateral: 300, debt: 0 });
+            assert_eq!(contract.mint_from_vault(100), Err(Error::PriceNotSet));
+
+            set_caller(AccountId::from([0x1; 32]));
+            assert_eq!(contract.set_collateral_price(PRICE_PRECISION), Ok(()));
+
+            set_caller(user);
+            assert_eq!(contract.mint_from_vault(201), Err(Error::Undercollateralized));
+            assert_eq!(contract.mint_from_vault(200), Ok(()));
+            assert_eq!(contract.mint_from_vault(Balance::MAX), Err(Error::Undercollateralized));
+            assert_eq!(contract.balance_of(user), 200);
+            assert_eq!(contract.total_supply(), 200);
+            assert_eq!(contract.withdraw_collateral(1), Err(Error::Undercollateralized));
+
+            assert_eq!(contract.repay(50), Ok(()));
+            assert_eq!(contract.vault_of(user), Vault { collateral: 300, debt: 150 });
+            assert_eq!(contract.total_supply(), 150);
+        }
+
+        #[ink::test]
+        fn vault_settings_emit_events() {
+            let mut contract = StableCurrency::new(0, "rsel".to_string());
+            let emitted = ink_env::test::recorded_events().count();
+            assert_eq!(contract.set_collateral_price(PRICE_PRECISION), Ok(()));
+            assert_eq!(contract.set_collateral_ratio(120), Ok(()));
+            assert_eq!(contract.set_liquidation_penalty(5), Ok(()));
+            assert_eq!(contract.set_price_oracle(None), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), emitted + 4);
+            assert_eq!(contract.collateral_price(), PRICE_PRECISION);
+
+            let vault = Vault { collateral: 120, debt: 100 };
+            assert!(StableCurrency::is_safe_at(&vault, PRICE_PRECISION, 120));
+            assert!(!StableCurrency::is_safe_at(&vault, PRICE_PRECISION - 1, 120));
+        }
+
+        #[ink::test]
+        fn liquidate_works() {
+            let mut contract = StableCurrency::new(500, "rsel".to_string());
+            let owner = AccountId::from([0x1; 32]);
+            let user = AccountId::from([0x2; 32]);
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(callee, 300)
+                .unwrap();
+            assert_eq!(contract.set_collateral_price(PRICE_PRECISION), Ok(()));
+
+            set_caller_with_value(user, 300);
+            assert_eq!(contract.open_vault(), Ok(()));
+            assert_eq!(contract.mint_from_vault(200), Ok(()));
+            assert_eq!(contract.liquidate(user), Err(Error::VaultNotLiquidatable));
+
+            set_caller(owner);
+            assert_eq!(contract.set_collateral_price(PRICE_PRECISION / 2), Ok(()));
+            assert_eq!(contract.liquidate(user), Ok(()));
+            assert_eq!(contract.balance_of(owner), 300);
+            assert_eq!(contract.vault_of(user), Vault { collateral: 0, debt: 0 });
+            assert_eq!(contract.total_supply(), 500);
+        }
+
         #[ink::test]
         fn createpayment_works() {
             let mut contract = StableCurrency::new(100,  "rsel".to_string());
             let buyer = AccountId::from([0x1; 32]);
             let seller = AccountId::from([0x0; 32]);
             assert_eq!(contract.balance_of(buyer), 100);
-            assert_eq!(contract.create_payment(seller, 30), Ok(()));
+            assert_eq!(contract.create_payment(seller, 30, Vec::new()), Ok(()));
             assert_eq!(contract.balance_of(buyer), 69);
             assert_eq!(contract.escrow_balance(buyer, seller), 31);
         }
@@ -374,21 +3491,52 @@ mod stable_currency {
             let mut contract = StableCurrency::new(100,  "rsel".to_string());
             let buyer = AccountId::from([0x1; 32]);
             let seller = AccountId::from([0x0; 32]);
-            assert_eq!(contract.create_payment(seller, 30), Ok(()));
+            assert_eq!(contract.create_payment(seller, 30, Vec::new()), Ok(()));
             assert_eq!(contract.balance_of(seller), 0);
-            assert_eq!(contract.complete_payment(buyer, seller), Ok(()));
+            assert_eq!(contract.complete_payment(buyer, seller), Ok(None));
             assert_eq!(contract.balance_of(seller), 30);
-            assert_eq!(contract.create_payment(seller, 30), Ok(()));
-            assert_eq!(contract.complete_payment(buyer, seller), Ok(()));
+            assert_eq!(contract.create_payment(seller, 30, Vec::new()), Ok(()));
+            assert_eq!(contract.complete_payment(buyer, seller), Ok(None));
             assert_eq!(contract.balance_of(seller), 60);
         }
 
+        #[ink::test]
+        fn fee_schedule_works() {
+            let mut contract = StableCurrency::new(20_000, "rsel".to_string());
+            let buyer = AccountId::from([0x1; 32]);
+            let seller = AccountId::from([0x0; 32]);
+            let treasury = AccountId::from([0x2; 32]);
+            assert_eq!(
+                contract.set_fee_schedule(50, 10, 5),
+                Err(Error::InvalidFeeSchedule)
+            );
+            assert_eq!(contract.set_fee_schedule(50, 5, 40), Ok(()));
+            assert_eq!(contract.fee_schedule(), (50, 5, 40));
+            assert_eq!(contract.set_treasury(treasury), Ok(()));
+
+            assert_eq!(contract.create_payment(seller, 100, Vec::new()), Ok(()));
+            assert_eq!(contract.escrow_balance(buyer, seller), 105);
+            assert_eq!(contract.complete_payment(buyer, seller), Ok(None));
+            assert_eq!(contract.balance_of(seller), 100);
+            assert_eq!(contract.balance_of(treasury), 5);
+
+            assert_eq!(contract.create_payment(seller, 10_000, Vec::new()), Ok(()));
+            assert_eq!(contract.escrow_balance(buyer, seller), 10_040);
+            assert_eq!(contract.refund(buyer, seller), Ok(()));
+
+            assert_eq!(contract.set_fee_exemption(buyer, true, false), Ok(()));
+            assert_eq!(contract.create_payment(seller, 100, Vec::new()), Ok(()));
+            assert_eq!(contract.escrow_balance(buyer, seller), 100);
+            assert_eq!(contract.complete_payment(buyer, seller), Ok(None));
+            assert_eq!(contract.balance_of(treasury), 5);
+        }
+
         #[ink::test]
         fn refund_work() {
             let mut contract = StableCurrency::new(100,  "rsel".to_string());
             let buyer = AccountId::from([0x1; 32]);
             let seller = AccountId::from([0x0; 32]);
-            assert_eq!(contract.create_payment(seller, 30), Ok(()));
+            assert_eq!(contract.create_payment(seller, 30, Vec::new()), Ok(()));
             assert_eq!(contract.balance_of(buyer), 69);
             assert_eq!(contract.refund(buyer, seller), Ok(()));
             assert_eq!(contract.balance_of(buyer), 100);